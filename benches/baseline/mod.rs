@@ -0,0 +1,133 @@
+//! The recursive matcher used before the patterns were merged into the intervals, kept only
+//! as the baseline for the benchmarks.
+
+use knife::Tokenizer;
+use std::{
+    cmp::Ordering,
+    iter::{Enumerate, Skip, Take},
+};
+
+#[derive(Debug, Clone, Copy)]
+enum Pattern {
+    Value(usize),
+    Range(usize, usize),
+}
+
+impl Pattern {
+    fn min(self) -> usize {
+        match self {
+            Pattern::Value(val) => val,
+            Pattern::Range(val, _) => val,
+        }
+    }
+
+    fn max(self) -> usize {
+        match self {
+            Pattern::Value(val) => val,
+            Pattern::Range(_, val) => val,
+        }
+    }
+}
+
+/// Matches the sorted, but not merged, patterns, moving forward over them recursively
+#[derive(Debug, Clone)]
+pub struct Matcher {
+    position: usize,
+    pattern: Vec<Pattern>,
+    min: usize,
+    max: usize,
+}
+
+impl Matcher {
+    pub fn new(pattern: &[knife::Pattern]) -> Self {
+        let mut pattern: Vec<Pattern> = pattern
+            .iter()
+            .map(|x| match *x {
+                knife::Pattern::Value(val) => Pattern::Value(val),
+                knife::Pattern::Range(min, max) => Pattern::Range(min, max),
+                _ => unimplemented!("only the fields and the ranges are benchmarked"),
+            })
+            .collect();
+        pattern.sort_by_key(|x| x.min());
+
+        let min = pattern.iter().map(|x| x.min()).min().unwrap_or(0);
+        let max = pattern.iter().map(|x| x.max()).max().unwrap_or(usize::MAX);
+
+        Self {
+            position: 0,
+            pattern,
+            min,
+            max,
+        }
+    }
+
+    #[inline]
+    fn matches(&mut self, index: usize) -> bool {
+        use Pattern::{Range, Value};
+
+        if self.position >= self.pattern.len() {
+            return false;
+        }
+
+        let pattern = self.pattern[self.position];
+        match pattern {
+            Value(ref val) => match index.cmp(val) {
+                Ordering::Less => false,
+                Ordering::Equal => {
+                    self.position += 1;
+                    true
+                }
+                Ordering::Greater => {
+                    self.position += 1;
+                    self.matches(index)
+                }
+            },
+            Range(min, max) => {
+                if index < min {
+                    false
+                } else if index < max {
+                    true
+                } else if index == max {
+                    self.position += 1;
+                    true
+                } else {
+                    self.position += 1;
+                    self.matches(index)
+                }
+            }
+        }
+    }
+
+    /// Extract the fields, like the knife did, cloning the matcher for each string
+    pub fn extract<'a>(&self, string: &'a str, tokenizer: &impl Tokenizer) -> Vec<&'a str> {
+        Filter::new(self.clone(), tokenizer.tokens(string)).collect()
+    }
+}
+
+struct Filter<I: Iterator> {
+    matcher: Matcher,
+    iterable: Skip<Take<Enumerate<I>>>,
+}
+
+impl<I: Iterator> Filter<I> {
+    fn new(matcher: Matcher, iterable: I) -> Self {
+        let iterable = iterable
+            .enumerate()
+            .take(matcher.max.saturating_add(1))
+            .skip(matcher.min);
+        Self { matcher, iterable }
+    }
+}
+
+impl<I: Iterator> Iterator for Filter<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (index, value) = self.iterable.next()?;
+        if self.matcher.matches(index) {
+            Some(value)
+        } else {
+            self.next()
+        }
+    }
+}
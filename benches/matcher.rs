@@ -0,0 +1,94 @@
+//! Benchmarks of the field selection, run with `cargo bench --bench matcher`.
+//!
+//! Each case is run with the current knife and with the recursive matcher it replaced,
+//! see the `baseline` module, so the report shows the change side by side.
+
+use criterion::{
+    black_box, criterion_group, criterion_main, measurement::WallTime, BenchmarkGroup, BenchmarkId,
+    Criterion,
+};
+use knife::{tokenizer::Whitespace, Knife, Pattern};
+
+mod baseline;
+
+/// Whitespace-delimited line with `n` fields
+fn wide_line(n: usize) -> String {
+    (0..n).map(|i| i.to_string()).collect::<Vec<_>>().join(" ")
+}
+
+/// Extract the fields selected by the `patterns` from the `line` with both matchers
+fn compare(group: &mut BenchmarkGroup<WallTime>, name: &str, line: &str, patterns: Vec<Pattern>) {
+    let old = baseline::Matcher::new(&patterns);
+    group.bench_function(BenchmarkId::new("baseline", name), |b| {
+        b.iter(|| old.extract(black_box(line), &Whitespace).len())
+    });
+
+    let knife = Knife::new(patterns);
+    group.bench_function(BenchmarkId::new("current", name), |b| {
+        b.iter(|| knife.extract(black_box(line)).len())
+    });
+}
+
+fn short_line(c: &mut Criterion) {
+    let short = wide_line(200);
+    let mut group = c.benchmark_group("short line");
+
+    compare(
+        &mut group,
+        "small spec",
+        &short,
+        vec![Pattern::Value(0), Pattern::Range(4, 9)],
+    );
+    compare(
+        &mut group,
+        "every other of 100 fields",
+        &short,
+        (0..100).step_by(2).map(Pattern::Value).collect(),
+    );
+    compare(
+        &mut group,
+        "5k duplicated values",
+        &short,
+        vec![Pattern::Value(10); 5_000],
+    );
+    group.finish();
+}
+
+fn wide_line_100k(c: &mut Criterion) {
+    let wide = wide_line(100_000);
+    let mut group = c.benchmark_group("wide line");
+    group.sample_size(20);
+
+    compare(
+        &mut group,
+        "unbounded range",
+        &wide,
+        vec![Pattern::Value(0), Pattern::Range(4999, usize::MAX)],
+    );
+    compare(
+        &mut group,
+        "20k values",
+        &wide,
+        (0..20_000).map(|i| Pattern::Value(i * 5)).collect(),
+    );
+    compare(
+        &mut group,
+        "20k overlapping ranges",
+        &wide,
+        (0..20_000)
+            .map(|i| Pattern::Range(i * 3, i * 3 + 10))
+            .collect(),
+    );
+    compare(
+        &mut group,
+        "20k nested ranges",
+        &wide,
+        (0..20_000)
+            .map(|i| Pattern::Range(i % 100, 90_000 - i))
+            .collect(),
+    );
+    group.finish();
+}
+
+criterion_group!(benches, short_line, wide_line_100k);
+criterion_main!(benches);
@@ -0,0 +1,43 @@
+//! Benchmarks of the tokenizers, run with `cargo bench --bench tokenizer`.
+//!
+//! The tokenizers return boxed iterators, so they can be picked at runtime, this compares
+//! extracting the fields through them with splitting the string directly.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use knife::{
+    tokenizer::{Quoted, Whitespace},
+    Knife, Matcher, Pattern, Tokenizer,
+};
+
+fn tokenizers(c: &mut Criterion) {
+    let line = "127.0.0.1 - - [10/Oct/2000:13:55:36 -0700] \"GET /a.gif HTTP/1.0\" 200 2326";
+    let patterns = vec![Pattern::Value(0), Pattern::Range(5, 7)];
+    let matcher = Matcher::new(patterns.clone()).unwrap();
+    let knife = Knife::new(patterns.clone());
+    let boxed =
+        Knife::new(patterns.clone()).with_tokenizer(Box::new(Whitespace) as Box<dyn Tokenizer>);
+    let quoted = Knife::new(patterns).with_tokenizer(Quoted);
+
+    let mut group = c.benchmark_group("short line");
+    group.bench_function("split_whitespace", |b| {
+        b.iter(|| {
+            matcher
+                .iter(black_box(line).split_whitespace())
+                .collect::<Vec<_>>()
+                .len()
+        })
+    });
+    group.bench_function("Whitespace", |b| {
+        b.iter(|| knife.extract(black_box(line)).len())
+    });
+    group.bench_function("boxed Whitespace", |b| {
+        b.iter(|| boxed.extract(black_box(line)).len())
+    });
+    group.bench_function("Quoted", |b| {
+        b.iter(|| quoted.extract(black_box(line)).len())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, tokenizers);
+criterion_main!(benches);
@@ -0,0 +1,62 @@
+/*
+ * C interface of the knife library, build it with `cargo build --release`
+ * and link against the `libknife` shared library.
+ */
+
+#ifndef KNIFE_H
+#define KNIFE_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Opaque handle holding the parsed spec */
+typedef struct knife knife_t;
+
+/* Status codes returned by the functions */
+typedef enum {
+    KNIFE_OK = 0,
+    /* the spec contains invalid characters */
+    KNIFE_CANNOT_PARSE = 1,
+    /* the fields are numbered starting at 1 */
+    KNIFE_STARTS_AT_ONE = 2,
+    /* the spec does not specify any fields */
+    KNIFE_EMPTY = 3,
+    KNIFE_NULL_POINTER = 4,
+    KNIFE_INVALID_UTF8 = 5,
+    /* the regular expression in the spec is not valid */
+    KNIFE_INVALID_REGEX = 6,
+} knife_error_t;
+
+/* Selected field, with its 1-based field number and the byte offsets of its start and end */
+typedef struct {
+    size_t field;
+    size_t start;
+    size_t end;
+} knife_span_t;
+
+/*
+ * Parse the null-terminated spec, like "1,3-5", and return the handle, or NULL on errors.
+ * The status is written to error unless it is NULL.
+ */
+knife_t *knife_new(const char *spec, knife_error_t *error);
+
+/*
+ * Extract the fields selected by the knife from the UTF-8 buffer of length bytes,
+ * the fields are delimited by whitespaces. Up to capacity spans are written to spans,
+ * and the number of all the selected fields to count, which can be larger than capacity.
+ */
+knife_error_t knife_extract(const knife_t *knife, const uint8_t *buffer, size_t length,
+                            knife_span_t *spans, size_t capacity, size_t *count);
+
+/* Free the handle returned by knife_new, passing NULL is allowed */
+void knife_free(knife_t *knife);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* KNIFE_H */
@@ -0,0 +1,135 @@
+use serde::Deserialize;
+use std::{collections::HashMap, env, fmt::Display, fs, io, path::PathBuf};
+
+/// Saved field pattern with the options used together with it
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Alias {
+    pub fields: String,
+    pub delimiter: Option<String>,
+    pub sub_delimiter: Option<String>,
+    pub output_separator: Option<String>,
+}
+
+/// Error in the config file
+#[derive(Debug, PartialEq)]
+pub struct Error(String);
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.trim_end())
+    }
+}
+
+/// Path to the config file, `$KNIFE_CONFIG` if set, otherwise `knife/config.toml`
+/// in `$XDG_CONFIG_HOME` or `~/.config`
+pub fn path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("KNIFE_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+    let dir = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(dir.join("knife").join("config.toml"))
+}
+
+/// Read the aliases from the config file
+pub fn load() -> Result<HashMap<String, Alias>, String> {
+    let Some(path) = path() else {
+        return Ok(HashMap::new());
+    };
+    match fs::read_to_string(&path) {
+        Ok(text) => parse(&text).map_err(|err| format!("{}: {}", path.display(), err)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(HashMap::new()),
+        Err(err) => Err(format!("{}: {}", path.display(), err)),
+    }
+}
+
+/// Parse the aliases from the TOML file, where the top-level `name = "pattern"` entries
+/// define the aliases, and the `[name]` tables define the aliases with the options:
+///
+/// ```toml
+/// ps = "2,11-"
+///
+/// [passwd]
+/// fields = "1,6"
+/// delimiter = ":"
+/// output-separator = "\t"
+/// ```
+pub fn parse(text: &str) -> Result<HashMap<String, Alias>, Error> {
+    let entries: toml::Table = toml::from_str(text).map_err(|err| Error(err.to_string()))?;
+    entries
+        .into_iter()
+        .map(|(name, entry)| {
+            let invalid = |msg: String| Error(format!("alias {}: {}", name, msg));
+            let alias = match entry {
+                toml::Value::String(fields) => Alias {
+                    fields,
+                    ..Default::default()
+                },
+                toml::Value::Table(table) => table
+                    .try_into()
+                    .map_err(|err: toml::de::Error| invalid(err.message().to_string()))?,
+                _ => return Err(invalid("expected the fields or a table".to_string())),
+            };
+            if alias.fields.trim().is_empty() {
+                return Err(invalid("no fields".to_string()));
+            }
+            Ok((name, alias))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, Alias, Error};
+    use test_case::test_case;
+
+    #[test]
+    fn aliases() {
+        let text = r#"
+            # processes
+            ps = "2,11-"
+            nginx-ip = '1'
+
+            [passwd]
+            fields = "1, 6"
+            delimiter = ":"
+            sub-delimiter = "="
+            output-separator = "\t" # tab
+
+            [tabs]
+            delimiter = "\t"
+            fields = "2"
+        "#;
+        let aliases = parse(text).unwrap();
+        assert_eq!(aliases.len(), 4);
+        assert_eq!(aliases["ps"].fields, "2,11-");
+        assert_eq!(aliases["nginx-ip"].fields, "1");
+        assert_eq!(
+            aliases["passwd"],
+            Alias {
+                fields: "1, 6".to_string(),
+                delimiter: Some(":".to_string()),
+                sub_delimiter: Some("=".to_string()),
+                output_separator: Some("\t".to_string()),
+            }
+        );
+        assert_eq!(aliases["tabs"].delimiter.as_deref(), Some("\t"));
+    }
+
+    #[test_case("ps = 2", "alias ps: expected the fields"; "not quoted")]
+    #[test_case("ps = \"2", "TOML parse error"; "unclosed quote")]
+    #[test_case("ps = \"2\" \"3\"", "TOML parse error"; "two strings")]
+    #[test_case("ps", "TOML parse error"; "no value")]
+    #[test_case("ps = \"1\"\nps = \"2\"", "duplicate key"; "duplicate")]
+    #[test_case("[ps\nfields = \"1\"", "TOML parse error"; "unclosed table")]
+    #[test_case("[ps]\nfields = \"1\"\ncolor = \"red\"", "alias ps: unknown field `color`"; "unknown option")]
+    #[test_case("[ps]\ndelimiter = \":\"", "alias ps: missing field `fields`"; "no fields")]
+    #[test_case("ps = \" \"", "alias ps: no fields"; "empty fields")]
+    fn invalid(text: &str, expected: &str) {
+        let err: Error = parse(text).unwrap_err();
+        assert!(err.to_string().contains(expected), "{}", err);
+    }
+}
@@ -0,0 +1,88 @@
+//! Selecting the fields by their content, using regular expressions.
+
+use crate::parser::Error;
+use regex::Regex;
+use std::fmt::Display;
+
+/// Selects the fields matching the regular expression, written as `/regex/`, or the fields
+/// following them, written as `/regex/+N`, where `N` is 1 by default
+#[derive(Debug, Clone)]
+pub struct Content {
+    regex: Regex,
+    offset: usize,
+}
+
+impl Content {
+    /// Select the fields `offset` positions after the ones matching the `regex`,
+    /// or the matching fields themselves if `offset` is 0
+    pub fn new(regex: &str, offset: usize) -> Result<Self, Error> {
+        let regex = Regex::new(regex).map_err(|err| Error::InvalidRegex(err.to_string()))?;
+        Ok(Self { regex, offset })
+    }
+
+    /// The regular expression matching the fields
+    pub fn regex(&self) -> &Regex {
+        &self.regex
+    }
+
+    /// How many fields after the matching one is selected
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Check if the field matches, return the 0-based index of the selected field
+    #[inline]
+    pub(crate) fn select(&self, index: usize, field: &str) -> Option<usize> {
+        if self.regex.is_match(field) {
+            index.checked_add(self.offset)
+        } else {
+            None
+        }
+    }
+}
+
+impl PartialEq for Content {
+    fn eq(&self, other: &Self) -> bool {
+        self.regex.as_str() == other.regex.as_str() && self.offset == other.offset
+    }
+}
+
+/// Display using the pattern language, the inverse of parsing
+impl Display for Content {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "/{}/", self.regex.as_str().replace('/', "\\/"))?;
+        match self.offset {
+            0 => Ok(()),
+            1 => write!(f, "+"),
+            n => write!(f, "+{}", n),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Content;
+    use crate::parser::Error;
+    use test_case::test_case;
+
+    #[test_case("^user:", 0, 3, "user:mary", Some(3); "matching")]
+    #[test_case("^user:", 0, 3, "id:7", None; "not matching")]
+    #[test_case("^-p$", 1, 3, "-p", Some(4); "next field")]
+    #[test_case("x", 2, 0, "x", Some(2); "further field")]
+    fn select(regex: &str, offset: usize, index: usize, field: &str, expected: Option<usize>) {
+        let content = Content::new(regex, offset).unwrap();
+        assert_eq!(content.select(index, field), expected);
+    }
+
+    #[test_case("^user:", 0, "/^user:/"; "matching")]
+    #[test_case("a/b", 1, "/a\\/b/+"; "next field")]
+    #[test_case("x", 3, "/x/+3"; "further field")]
+    fn display(regex: &str, offset: usize, expected: &str) {
+        assert_eq!(Content::new(regex, offset).unwrap().to_string(), expected);
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(matches!(Content::new("(", 0), Err(Error::InvalidRegex(_))));
+    }
+}
@@ -0,0 +1,96 @@
+use crate::process::{Cutter, Process};
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+};
+
+/// Counts the lines with the same selected fields, like `sort | uniq -c | sort -rn`,
+/// but the input does not need to be sorted
+pub struct Count<'a> {
+    knife: &'a Cutter,
+    sep: &'a str,
+    top: Option<usize>,
+    ascending: bool,
+    /// The counts of the groups, and when they were first seen, so the ties keep that order
+    groups: HashMap<Vec<String>, (usize, usize)>,
+}
+
+impl<'a> Count<'a> {
+    /// Print only the `top` most common groups, or the least common ones if `ascending`
+    pub fn new(knife: &'a Cutter, sep: &'a str, top: Option<usize>, ascending: bool) -> Self {
+        Self {
+            knife,
+            sep,
+            top,
+            ascending,
+            groups: HashMap::new(),
+        }
+    }
+}
+
+impl Process for Count<'_> {
+    fn line(&mut self, line: &str, _out: &mut dyn Write) -> io::Result<bool> {
+        let fields = self.knife.extract(line);
+        if fields.is_empty() {
+            return Ok(false);
+        }
+        let key: Vec<String> = fields.into_iter().map(String::from).collect();
+        let seen = self.groups.len();
+        self.groups.entry(key).or_insert((seen, 0)).1 += 1;
+        Ok(true)
+    }
+
+    /// Write the counts followed by the fields
+    fn finish(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        let mut groups: Vec<_> = self.groups.drain().collect();
+        if self.ascending {
+            groups.sort_unstable_by_key(|&(_, (seen, count))| (count, seen));
+        } else {
+            groups.sort_unstable_by_key(|&(_, (seen, count))| (std::cmp::Reverse(count), seen));
+        }
+        let top = self.top.unwrap_or(usize::MAX);
+        for (fields, (_, count)) in groups.iter().take(top) {
+            writeln!(out, "{}{}{}", count, self.sep, fields.join(self.sep))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Count;
+    use crate::process::{Cutter, Process};
+    use knife::Knife;
+    use std::str::FromStr;
+    use test_case::test_case;
+
+    const TEXT: &str = "GET /a 200\nPOST /b 500\nGET /a 200\nGET /c 404\nGET /a 304\nPOST /b 500\n";
+
+    fn count(spec: &str, top: Option<usize>, ascending: bool) -> (String, bool) {
+        let knife: Cutter = Knife::from_str(spec)
+            .unwrap()
+            .with_tokenizer(Box::new(knife::tokenizer::Whitespace));
+        let mut count = Count::new(&knife, " ", top, ascending);
+        let mut out = Vec::new();
+        let mut extracted = false;
+        for line in TEXT.lines() {
+            extracted |= count.line(line, &mut out).unwrap();
+        }
+        count.finish(&mut out).unwrap();
+        (String::from_utf8(out).unwrap(), extracted)
+    }
+
+    #[test_case("1", None, false, "4 GET\n2 POST\n"; "single field")]
+    #[test_case("1-2", None, false, "3 GET /a\n2 POST /b\n1 GET /c\n"; "tuple")]
+    #[test_case("3", None, true, "1 404\n1 304\n2 200\n2 500\n"; "ascending")]
+    #[test_case("3", Some(1), false, "2 200\n"; "top")]
+    #[test_case("2", Some(10), false, "3 /a\n2 /b\n1 /c\n"; "top above the groups")]
+    fn counts(spec: &str, top: Option<usize>, ascending: bool, expected: &str) {
+        assert_eq!(count(spec, top, ascending), (expected.to_string(), true));
+    }
+
+    #[test]
+    fn nothing_extracted() {
+        assert_eq!(count("7", None, false), (String::new(), false));
+    }
+}
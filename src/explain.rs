@@ -0,0 +1,203 @@
+//! Explanations of the patterns in plain words.
+
+use crate::{
+    matcher::Pattern,
+    parser::{self, Error},
+    Knife,
+};
+use std::fmt::Display;
+
+/// Possible mistakes found in the pattern
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Warning {
+    /// The range was given in the reversed order, like `5-2`, so it was swapped
+    Reversed(Pattern),
+    /// The first pattern selects only the fields already selected by the second one
+    Redundant(Pattern, Pattern),
+    /// The patterns select some of the same fields
+    Overlap(Pattern, Pattern),
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use Warning::*;
+        match *self {
+            Reversed(pattern) => write!(
+                f,
+                "the range {}-{} was swapped to {}",
+                pattern.max() + 1,
+                pattern.min() + 1,
+                pattern
+            ),
+            Redundant(pattern, other) if pattern == other => {
+                write!(f, "{} is given more than once", pattern)
+            }
+            Redundant(pattern, other) => {
+                write!(
+                    f,
+                    "{} is redundant, it is already selected by {}",
+                    pattern, other
+                )
+            }
+            Overlap(first, second) => write!(f, "{} overlaps with {}", first, second),
+        }
+    }
+}
+
+/// The fields selected by the pattern and the possible mistakes found in it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Explanation {
+    knife: Knife,
+    warnings: Vec<Warning>,
+}
+
+impl Explanation {
+    /// The knife selecting the fields, its patterns are normalized
+    pub fn knife(&self) -> &Knife {
+        &self.knife
+    }
+
+    /// The possible mistakes found in the pattern
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+}
+
+/// Describe the fields in plain words, like "fields 1 to 3, field 5, fields 9 to end"
+impl Display for Explanation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let patterns = self.knife.patterns();
+        for (i, pattern) in patterns.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            match *pattern {
+                Pattern::Value(val) => write!(f, "field {}", val + 1)?,
+                Pattern::Range(0, usize::MAX) => write!(f, "all fields")?,
+                Pattern::Range(min, usize::MAX) => write!(f, "fields {} to end", min + 1)?,
+                Pattern::Range(min, max) => write!(f, "fields {} to {}", min + 1, max + 1)?,
+                Pattern::Sub(field, sub) => {
+                    write!(f, "sub-field {} of field {}", sub + 1, field + 1)?
+                }
+            }
+        }
+        for (i, content) in self.knife.content().iter().enumerate() {
+            if i > 0 || !patterns.is_empty() {
+                write!(f, ", ")?;
+            }
+            let regex = content.regex().as_str();
+            match content.offset() {
+                0 => write!(f, "fields matching /{}/", regex)?,
+                1 => write!(f, "fields after the ones matching /{}/", regex)?,
+                n => write!(f, "fields {} after the ones matching /{}/", n, regex)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parse the pattern, like `parser::with_content`, and explain it
+pub fn explain(s: &str) -> Result<Explanation, Error> {
+    let mut warnings = Vec::new();
+    let mut reversed = Vec::new();
+    let (patterns, content) = parser::parse_with_content(s, &mut reversed)?;
+    warnings.extend(reversed.into_iter().map(Warning::Reversed));
+    warnings.extend(overlaps(&patterns));
+
+    Ok(Explanation {
+        knife: Knife::new(patterns).with_content(content),
+        warnings,
+    })
+}
+
+/// Find the patterns overlapping with the others, sweeping over them in the order of
+/// their starts, and comparing with the one reaching the furthest so far
+fn overlaps(patterns: &[Pattern]) -> Vec<Warning> {
+    let (subs, mut sorted): (Vec<Pattern>, Vec<Pattern>) =
+        patterns.iter().partition(|x| matches!(x, Pattern::Sub(..)));
+    // the wider patterns go first, so the narrower ones are reported as redundant
+    sorted.sort_by_key(|x| (x.min(), std::cmp::Reverse(x.max())));
+
+    let mut warnings = redundant_subs(&subs, patterns);
+    let mut iter = sorted.into_iter();
+    let Some(mut furthest) = iter.next() else {
+        return warnings;
+    };
+    for next in iter {
+        if next.max() <= furthest.max() {
+            warnings.push(Warning::Redundant(next, furthest));
+        } else {
+            if next.min() <= furthest.max() {
+                warnings.push(Warning::Overlap(furthest, next));
+            }
+            furthest = next;
+        }
+    }
+    warnings
+}
+
+/// Find the sub-fields given more than once, or of the fields selected as a whole
+fn redundant_subs(subs: &[Pattern], patterns: &[Pattern]) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+    for (i, &sub) in subs.iter().enumerate() {
+        let whole = patterns.iter().find(|x| {
+            !matches!(x, Pattern::Sub(..)) && x.min() <= sub.min() && sub.min() <= x.max()
+        });
+        if let Some(&whole) = whole {
+            warnings.push(Warning::Redundant(sub, whole));
+        } else if subs[..i].contains(&sub) {
+            warnings.push(Warning::Redundant(sub, sub));
+        }
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{explain, Warning};
+    use crate::matcher::Pattern::{Range, Sub, Value};
+    use test_case::test_case;
+
+    #[test_case("1", "field 1"; "single field")]
+    #[test_case("-", "all fields"; "all fields")]
+    #[test_case("9-, 5, -3", "fields 1 to 3, field 5, fields 9 to end"; "sorted")]
+    #[test_case("1,2,3, 5-7, 6-8", "fields 1 to 3, fields 5 to 8"; "merged")]
+    #[test_case("3.2, 1", "field 1, sub-field 2 of field 3"; "sub-field")]
+    #[test_case("/^a/, 2", "field 2, fields matching /^a/"; "content")]
+    #[test_case("/^-p$/+, /x/+3", "fields after the ones matching /^-p$/, fields 3 after the ones matching /x/"; "offset")]
+    fn describe(input: &str, expected: &str) {
+        assert_eq!(explain(input).unwrap().to_string(), expected);
+    }
+
+    #[test_case("1, 3-5, 7-", &[]; "no warnings")]
+    #[test_case("1-3, 4-5", &[]; "adjacent")]
+    #[test_case("5-2", &[Warning::Reversed(Range(1, 4))]; "reversed")]
+    #[test_case("1, 1-3", &[Warning::Redundant(Value(0), Range(0, 2))]; "redundant value")]
+    #[test_case("2, 2", &[Warning::Redundant(Value(1), Value(1))]; "duplicate")]
+    #[test_case("4-6, 1-9", &[Warning::Redundant(Range(3, 5), Range(0, 8))]; "redundant range")]
+    #[test_case("1-3, 2-5", &[Warning::Overlap(Range(0, 2), Range(1, 4))]; "overlap")]
+    #[test_case("3.1, 3.2", &[]; "sub-fields")]
+    #[test_case("3.1, 2-4", &[Warning::Redundant(Sub(2, 0), Range(1, 3))]; "sub-field of whole field")]
+    #[test_case("3.1, 3.1", &[Warning::Redundant(Sub(2, 0), Sub(2, 0))]; "duplicate sub-field")]
+    #[test_case(
+        "6-4, 1-5",
+        &[Warning::Reversed(Range(3, 5)), Warning::Overlap(Range(0, 4), Range(3, 5))];
+        "reversed and overlap"
+    )]
+    fn warnings(input: &str, expected: &[Warning]) {
+        assert_eq!(explain(input).unwrap().warnings(), expected);
+    }
+
+    #[test_case(Warning::Reversed(Range(1, 4)), "the range 5-2 was swapped to 2-5"; "reversed")]
+    #[test_case(Warning::Redundant(Value(0), Range(0, 2)), "1 is redundant, it is already selected by 1-3"; "redundant")]
+    #[test_case(Warning::Redundant(Value(1), Value(1)), "2 is given more than once"; "duplicate")]
+    #[test_case(Warning::Overlap(Range(0, 2), Range(1, usize::MAX)), "1-3 overlaps with 2-"; "overlap")]
+    fn display(warning: Warning, expected: &str) {
+        assert_eq!(warning.to_string(), expected);
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(explain("0-5").is_err());
+    }
+}
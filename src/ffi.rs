@@ -0,0 +1,222 @@
+//! C ABI for using knife from other languages, see `include/knife.h` for the C declarations.
+//!
+//! The spans are extracted by [`Knife::extract_spans`], so the fields are delimited by whitespaces
+//! and the offsets are in bytes.
+
+use crate::{parser::Error, Knife};
+use std::{
+    ffi::{c_char, CStr},
+    ptr, slice,
+    str::FromStr,
+};
+
+/// Status codes returned by the functions
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KnifeError {
+    Ok = 0,
+    CannotParse = 1,
+    StartsAtOne = 2,
+    Empty = 3,
+    NullPointer = 4,
+    InvalidUtf8 = 5,
+    InvalidRegex = 6,
+}
+
+impl From<Error> for KnifeError {
+    fn from(value: Error) -> Self {
+        match value {
+            Error::CannotParse => KnifeError::CannotParse,
+            Error::StartsAtOne => KnifeError::StartsAtOne,
+            Error::Empty => KnifeError::Empty,
+            Error::InvalidRegex(_) => KnifeError::InvalidRegex,
+            // not raised when parsing the knives, they support everything
+            Error::NotPositional => KnifeError::CannotParse,
+        }
+    }
+}
+
+/// Selected field, with its 1-based field number and the byte offsets of its start and end
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct KnifeSpan {
+    pub field: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Write the status to the `error` pointer, if it is not null
+unsafe fn report(error: *mut KnifeError, status: KnifeError) {
+    if !error.is_null() {
+        *error = status;
+    }
+}
+
+/// Parse the null-terminated `spec` and return the handle, or null on errors,
+/// the status is written to `error` unless it is null
+///
+/// # Safety
+///
+/// `spec` needs to be a null-terminated string, `error` needs to be null or point to `KnifeError`.
+#[no_mangle]
+pub unsafe extern "C" fn knife_new(spec: *const c_char, error: *mut KnifeError) -> *mut Knife {
+    if spec.is_null() {
+        report(error, KnifeError::NullPointer);
+        return ptr::null_mut();
+    }
+    let Ok(spec) = CStr::from_ptr(spec).to_str() else {
+        report(error, KnifeError::InvalidUtf8);
+        return ptr::null_mut();
+    };
+    match Knife::from_str(spec) {
+        Ok(knife) => {
+            report(error, KnifeError::Ok);
+            Box::into_raw(Box::new(knife))
+        }
+        Err(err) => {
+            report(error, err.into());
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Extract the fields selected by the `knife` from the UTF-8 `buffer` of `length` bytes,
+/// write up to `capacity` of them to `spans`, and the number of all the selected fields
+/// to `count`, which can be larger than `capacity`
+///
+/// # Safety
+///
+/// `knife` needs to be a handle returned by `knife_new`, `buffer` needs to point to at least
+/// `length` bytes, `spans` to at least `capacity` of `KnifeSpan`s, and `count` to `usize`.
+#[no_mangle]
+pub unsafe extern "C" fn knife_extract(
+    knife: *const Knife,
+    buffer: *const u8,
+    length: usize,
+    spans: *mut KnifeSpan,
+    capacity: usize,
+    count: *mut usize,
+) -> KnifeError {
+    if knife.is_null()
+        || count.is_null()
+        || (buffer.is_null() && length > 0)
+        || (spans.is_null() && capacity > 0)
+    {
+        return KnifeError::NullPointer;
+    }
+    let buffer = if length > 0 {
+        slice::from_raw_parts(buffer, length)
+    } else {
+        &[]
+    };
+    let Ok(string) = std::str::from_utf8(buffer) else {
+        return KnifeError::InvalidUtf8;
+    };
+
+    let fields = (*knife).extract_spans(string);
+    if capacity > 0 {
+        let spans = slice::from_raw_parts_mut(spans, capacity);
+        for (span, (field, range, _)) in spans.iter_mut().zip(&fields) {
+            *span = KnifeSpan {
+                field: *field,
+                start: range.start,
+                end: range.end,
+            };
+        }
+    }
+    *count = fields.len();
+    KnifeError::Ok
+}
+
+/// Free the handle returned by `knife_new`, passing null is allowed
+///
+/// # Safety
+///
+/// `knife` needs to be null or a handle returned by `knife_new` that was not freed before.
+#[no_mangle]
+pub unsafe extern "C" fn knife_free(knife: *mut Knife) {
+    if !knife.is_null() {
+        drop(Box::from_raw(knife));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{knife_extract, knife_free, knife_new, KnifeError, KnifeSpan};
+    use std::{ffi::CString, ptr};
+    use test_case::test_case;
+
+    #[test_case("1,3-", KnifeError::Ok; "valid")]
+    #[test_case("0", KnifeError::StartsAtOne; "starts at one")]
+    #[test_case("x", KnifeError::CannotParse; "cannot parse")]
+    #[test_case("", KnifeError::Empty; "empty")]
+    #[test_case("/(/", KnifeError::InvalidRegex; "invalid regex")]
+    fn new(spec: &str, expected: KnifeError) {
+        let spec = CString::new(spec).unwrap();
+        let mut error = KnifeError::NullPointer;
+        unsafe {
+            let knife = knife_new(spec.as_ptr(), &mut error);
+            assert_eq!(error, expected);
+            assert_eq!(knife.is_null(), expected != KnifeError::Ok);
+            knife_free(knife);
+        }
+    }
+
+    #[test]
+    fn extract() {
+        let spec = CString::new("2-3,5").unwrap();
+        let line = "Mary  had a little lamb.";
+        let mut spans = [KnifeSpan::default(); 2];
+        let mut count = 0;
+        unsafe {
+            let knife = knife_new(spec.as_ptr(), ptr::null_mut());
+            let status = knife_extract(
+                knife,
+                line.as_ptr(),
+                line.len(),
+                spans.as_mut_ptr(),
+                spans.len(),
+                &mut count,
+            );
+            knife_free(knife);
+            assert_eq!(status, KnifeError::Ok);
+        }
+        assert_eq!(count, 3, "counts all the fields");
+        assert_eq!(
+            spans,
+            [
+                KnifeSpan {
+                    field: 2,
+                    start: 6,
+                    end: 9
+                },
+                KnifeSpan {
+                    field: 3,
+                    start: 10,
+                    end: 11
+                },
+            ],
+            "but writes only up to the capacity"
+        );
+    }
+
+    #[test]
+    fn extract_invalid_input() {
+        let spec = CString::new("1").unwrap();
+        let mut count = 0;
+        unsafe {
+            let knife = knife_new(spec.as_ptr(), ptr::null_mut());
+            let invalid = [0xff, 0xfe];
+            let status = knife_extract(knife, invalid.as_ptr(), 2, ptr::null_mut(), 0, &mut count);
+            assert_eq!(status, KnifeError::InvalidUtf8);
+
+            let status = knife_extract(knife, ptr::null(), 0, ptr::null_mut(), 0, &mut count);
+            assert_eq!(status, KnifeError::Ok);
+            assert_eq!(count, 0);
+
+            let status = knife_extract(knife, ptr::null(), 5, ptr::null_mut(), 0, &mut count);
+            assert_eq!(status, KnifeError::NullPointer);
+            knife_free(knife);
+        }
+    }
+}
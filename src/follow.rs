@@ -0,0 +1,223 @@
+use std::{
+    fs::{self, File, Metadata},
+    io::{self, BufRead, BufReader, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+/// Reads the lines appended to the file, following it by name, so when the file is
+/// rotated, the new file with the same name is opened, and when it is truncated,
+/// it is read again from the start
+#[derive(Debug)]
+pub struct Follower {
+    path: PathBuf,
+    reader: Option<BufReader<File>>,
+    id: Option<FileId>,
+    position: u64,
+    partial: Vec<u8>,
+}
+
+impl Follower {
+    /// Start following the file, it does not need to exist yet
+    pub fn new(path: &Path) -> Self {
+        let mut follower = Self {
+            path: path.to_path_buf(),
+            reader: None,
+            id: None,
+            position: 0,
+            partial: Vec::new(),
+        };
+        let _ = follower.reopen();
+        follower
+    }
+
+    /// Check if the file is opened
+    pub fn is_open(&self) -> bool {
+        self.reader.is_some()
+    }
+
+    /// Open the file by the name and start reading it from the beginning
+    fn reopen(&mut self) -> io::Result<()> {
+        self.reader = None;
+        let file = File::open(&self.path)?;
+        self.id = file_id(&file.metadata()?);
+        self.reader = Some(BufReader::new(file));
+        self.position = 0;
+        self.partial.clear();
+        Ok(())
+    }
+
+    /// Read the complete lines appended since the last call, the incomplete last line
+    /// is kept until it is completed
+    pub fn poll(&mut self, lines: &mut Vec<String>) -> io::Result<()> {
+        if self.reader.is_none() && self.reopen().is_err() {
+            // the file does not exist (yet), wait for it
+            return Ok(());
+        }
+        self.read_lines(lines)?;
+
+        let Ok(metadata) = fs::metadata(&self.path) else {
+            // the file was removed, keep the opened one until it appears again
+            return Ok(());
+        };
+        if file_id(&metadata) != self.id {
+            // the file was rotated, we already read everything from the old one
+            if !self.partial.is_empty() {
+                lines.push(to_string(&self.partial)?);
+            }
+            self.reopen()?;
+            self.read_lines(lines)?;
+        } else if metadata.len() < self.position {
+            // the file was truncated
+            if let Some(ref mut reader) = self.reader {
+                reader.seek(SeekFrom::Start(0))?;
+            }
+            self.position = 0;
+            self.partial.clear();
+            self.read_lines(lines)?;
+        }
+        Ok(())
+    }
+
+    /// Read the complete lines until the end of the file
+    fn read_lines(&mut self, lines: &mut Vec<String>) -> io::Result<()> {
+        let Some(ref mut reader) = self.reader else {
+            return Ok(());
+        };
+        loop {
+            let size = reader.read_until(b'\n', &mut self.partial)?;
+            if size == 0 {
+                return Ok(());
+            }
+            self.position += size as u64;
+            if self.partial.ends_with(b"\n") {
+                self.partial.pop();
+                if self.partial.ends_with(b"\r") {
+                    self.partial.pop();
+                }
+                let line = to_string(&self.partial);
+                self.partial.clear();
+                lines.push(line?);
+            }
+        }
+    }
+}
+
+fn to_string(bytes: &[u8]) -> io::Result<String> {
+    String::from_utf8(bytes.to_vec()).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "stream did not contain valid UTF-8",
+        )
+    })
+}
+
+/// Identifies the file, regardless of its name
+type FileId = (u64, u64);
+
+#[cfg(unix)]
+fn file_id(metadata: &Metadata) -> Option<FileId> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_metadata: &Metadata) -> Option<FileId> {
+    // rotation can be detected only as truncation
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Follower;
+    use std::{
+        fs::{self, OpenOptions},
+        io::Write,
+        path::PathBuf,
+    };
+
+    /// Path to a new file in a temporary directory unique for the test
+    fn temp_path(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("knife-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("test.log")
+    }
+
+    fn append(path: &PathBuf, text: &str) {
+        let mut file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(path)
+            .unwrap();
+        file.write_all(text.as_bytes()).unwrap();
+    }
+
+    fn poll(follower: &mut Follower) -> Vec<String> {
+        let mut lines = Vec::new();
+        follower.poll(&mut lines).unwrap();
+        lines
+    }
+
+    #[test]
+    fn appended_lines() {
+        let path = temp_path("appended");
+        append(&path, "a b\nc d\n");
+
+        let mut follower = Follower::new(&path);
+        assert_eq!(poll(&mut follower), ["a b", "c d"]);
+        assert!(poll(&mut follower).is_empty());
+
+        append(&path, "e f\r\ng");
+        assert_eq!(poll(&mut follower), ["e f"]);
+        append(&path, " h\n");
+        assert_eq!(
+            poll(&mut follower),
+            ["g h"],
+            "incomplete line was completed"
+        );
+    }
+
+    #[test]
+    fn file_created_later() {
+        let path = temp_path("created");
+        let mut follower = Follower::new(&path);
+        assert!(!follower.is_open());
+        assert!(poll(&mut follower).is_empty());
+
+        append(&path, "a\n");
+        assert_eq!(poll(&mut follower), ["a"]);
+    }
+
+    #[test]
+    fn truncated() {
+        let path = temp_path("truncated");
+        append(&path, "a\nb\n");
+
+        let mut follower = Follower::new(&path);
+        assert_eq!(poll(&mut follower), ["a", "b"]);
+
+        fs::write(&path, "c\n").unwrap();
+        assert_eq!(poll(&mut follower), ["c"]);
+    }
+
+    #[test]
+    fn rotated() {
+        let path = temp_path("rotated");
+        append(&path, "a\n");
+
+        let mut follower = Follower::new(&path);
+        assert_eq!(poll(&mut follower), ["a"]);
+
+        append(&path, "b\n");
+        fs::rename(&path, path.with_extension("log.1")).unwrap();
+        append(&path, "c\nd\n");
+        assert_eq!(
+            poll(&mut follower),
+            ["b", "c", "d"],
+            "read the old file till end"
+        );
+
+        append(&path, "e\n");
+        assert_eq!(poll(&mut follower), ["e"]);
+    }
+}
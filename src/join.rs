@@ -0,0 +1,307 @@
+use crate::process::{Cutter, Process};
+use knife::{parser, Matcher, Pattern, Tokenizer};
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Write},
+    str::FromStr,
+};
+
+/// Which lines are printed when joining
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum Kind {
+    /// Only the lines with the matching keys in both inputs
+    Inner,
+    /// Also the lines of the input without the matching lines in the joined file
+    Left,
+    /// Also the lines of both inputs without the matching lines in the other one
+    Full,
+}
+
+/// The key fields, unlike in [`Matcher`], they are kept in the order they were given,
+/// so `2,1` can be matched with `1,2` of the other file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Key(Vec<(usize, usize)>);
+
+impl Key {
+    /// The key fields of the line, `None` if the line lacks any of them
+    fn fields(&self, tokenizer: &dyn Tokenizer, line: &str) -> Option<Vec<String>> {
+        let last = self.0.iter().map(|x| x.1).max().unwrap_or(0);
+        let tokens: Vec<&str> = tokenizer
+            .tokens(line)
+            .take(last.saturating_add(1))
+            .collect();
+        let mut key = Vec::new();
+        for &(min, max) in &self.0 {
+            if min >= tokens.len() {
+                return None;
+            }
+            let end = if max == usize::MAX {
+                tokens.len()
+            } else {
+                max + 1
+            };
+            key.extend(tokens.get(min..end)?.iter().map(|x| x.to_string()));
+        }
+        Some(key)
+    }
+}
+
+/// Only the field numbers and ranges can be used as the keys
+impl FromStr for Key {
+    type Err = parser::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (patterns, content) = parser::with_content(s)?;
+        if !content.is_empty() {
+            return Err(parser::Error::NotPositional);
+        }
+        patterns
+            .into_iter()
+            .map(|x| match x {
+                Pattern::Value(index) => Ok((index, index)),
+                Pattern::Range(min, max) => Ok((min, max)),
+                _ => Err(parser::Error::NotPositional),
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+/// Line of the joined file
+struct Row {
+    fields: String,
+    matched: bool,
+}
+
+/// Joins the lines of the input with the lines of another file having the same key,
+/// the joined file is held in memory, so neither needs to be sorted, the lines lacking
+/// any of the key fields are never matched
+pub struct Join<'a> {
+    knife: &'a Cutter,
+    sep: &'a str,
+    kind: Kind,
+    key: Key,
+    rows: Vec<Row>,
+    index: HashMap<Vec<String>, Vec<usize>>,
+}
+
+impl<'a> Join<'a> {
+    /// Read the joined file, the lines are matched by the `other_key` fields with the `key`
+    /// fields of the input, the `other_fields` are printed after the fields selected by the `knife`
+    pub fn new(
+        knife: &'a Cutter,
+        sep: &'a str,
+        kind: Kind,
+        key: Key,
+        other_key: &Key,
+        other_fields: &Matcher,
+        reader: impl BufRead,
+    ) -> io::Result<Self> {
+        let tokenizer = knife.tokenizer();
+        let mut rows = Vec::new();
+        let mut index: HashMap<Vec<String>, Vec<usize>> = HashMap::new();
+        for line in reader.lines() {
+            let line = line?;
+            let fields: Vec<&str> = other_fields.iter(tokenizer.tokens(&line)).collect();
+            if let Some(key) = other_key.fields(tokenizer, &line) {
+                index.entry(key).or_default().push(rows.len());
+            }
+            rows.push(Row {
+                fields: fields.join(sep),
+                matched: false,
+            });
+        }
+        Ok(Self {
+            knife,
+            sep,
+            kind,
+            key,
+            rows,
+            index,
+        })
+    }
+}
+
+/// Write the fields of both sides, skipping the empty ones, so there is no dangling separator
+fn write_joined(out: &mut dyn Write, sep: &str, left: &str, right: &str) -> io::Result<bool> {
+    let line = match (left.is_empty(), right.is_empty()) {
+        (true, true) => return Ok(false),
+        (false, true) => left.to_string(),
+        (true, false) => right.to_string(),
+        (false, false) => format!("{}{}{}", left, sep, right),
+    };
+    out.write_all(line.as_bytes())?;
+    out.write_all(b"\n")?;
+    Ok(true)
+}
+
+impl Process for Join<'_> {
+    fn line(&mut self, line: &str, out: &mut dyn Write) -> io::Result<bool> {
+        let left = self.knife.extract(line).join(self.sep);
+        let matches = self
+            .key
+            .fields(self.knife.tokenizer(), line)
+            .and_then(|key| self.index.get(&key));
+        let Some(matches) = matches else {
+            if self.kind == Kind::Inner {
+                return Ok(false);
+            }
+            return write_joined(out, self.sep, &left, "");
+        };
+
+        let mut extracted = false;
+        for &i in matches {
+            let row = &mut self.rows[i];
+            row.matched = true;
+            extracted |= write_joined(out, self.sep, &left, &row.fields)?;
+        }
+        Ok(extracted)
+    }
+
+    /// Write the lines of the joined file that were not matched, for the full join
+    fn finish(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        if self.kind != Kind::Full {
+            return Ok(());
+        }
+        for row in self.rows.iter().filter(|row| !row.matched) {
+            write_joined(out, self.sep, "", &row.fields)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Join, Key, Kind};
+    use crate::process::{Cutter, Process};
+    use knife::{Knife, Matcher};
+    use std::str::FromStr;
+    use test_case::test_case;
+
+    const PROCESSES: &str = "1 init root\n42 sshd root\n77 vim mary\n";
+    const USAGE: &str = "77 12%\n42 1%\n42 3%\n99 50%\n";
+
+    fn matcher(spec: &str) -> Matcher {
+        Knife::from_str(spec).unwrap().matcher().clone()
+    }
+
+    fn key(spec: &str) -> Key {
+        Key::from_str(spec).unwrap()
+    }
+
+    fn join(kind: Kind, other_fields: &str) -> (String, bool) {
+        let knife: Cutter = Knife::from_str("1-2")
+            .unwrap()
+            .with_tokenizer(Box::new(knife::tokenizer::Whitespace));
+        let mut join = Join::new(
+            &knife,
+            " ",
+            kind,
+            key("1"),
+            &key("1"),
+            &matcher(other_fields),
+            USAGE.as_bytes(),
+        )
+        .unwrap();
+
+        let mut out = Vec::new();
+        let mut extracted = false;
+        for line in PROCESSES.lines() {
+            extracted |= join.line(line, &mut out).unwrap();
+        }
+        join.finish(&mut out).unwrap();
+        (String::from_utf8(out).unwrap(), extracted)
+    }
+
+    #[test_case(Kind::Inner, "42 sshd 1%\n42 sshd 3%\n77 vim 12%\n"; "inner")]
+    #[test_case(Kind::Left, "1 init\n42 sshd 1%\n42 sshd 3%\n77 vim 12%\n"; "left")]
+    #[test_case(Kind::Full, "1 init\n42 sshd 1%\n42 sshd 3%\n77 vim 12%\n50%\n"; "full")]
+    fn kinds(kind: Kind, expected: &str) {
+        assert_eq!(join(kind, "2"), (expected.to_string(), true));
+    }
+
+    #[test]
+    fn all_fields() {
+        let (out, _) = join(Kind::Inner, "-");
+        assert_eq!(out, "42 sshd 42 1%\n42 sshd 42 3%\n77 vim 77 12%\n");
+    }
+
+    #[test]
+    fn nothing_matched() {
+        let knife: Cutter = Knife::from_str("1")
+            .unwrap()
+            .with_tokenizer(Box::new(knife::tokenizer::Whitespace));
+        let mut join = Join::new(
+            &knife,
+            " ",
+            Kind::Inner,
+            key("1"),
+            &key("2"),
+            &matcher("-"),
+            USAGE.as_bytes(),
+        )
+        .unwrap();
+        let mut out = Vec::new();
+        assert!(!join.line("42 sshd", &mut out).unwrap());
+        assert!(!join.line("", &mut out).unwrap(), "no key");
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn multiple_keys() {
+        let knife: Cutter = Knife::from_str("3")
+            .unwrap()
+            .with_tokenizer(Box::new(knife::tokenizer::Delimiter(',')));
+        let other = "a,1,x\na,2,y\n";
+        let mut join = Join::new(
+            &knife,
+            ",",
+            Kind::Inner,
+            key("1-2"),
+            &key("1-2"),
+            &matcher("3"),
+            other.as_bytes(),
+        )
+        .unwrap();
+        let mut out = Vec::new();
+        join.line("a,2,b", &mut out).unwrap();
+        join.line("a,3,c", &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "b,y\n");
+    }
+
+    fn keyed(key_spec: &str, other_key: &str, other: &str, line: &str) -> String {
+        let knife: Cutter = Knife::from_str("1")
+            .unwrap()
+            .with_tokenizer(Box::new(knife::tokenizer::Whitespace));
+        let mut join = Join::new(
+            &knife,
+            " ",
+            Kind::Inner,
+            key(key_spec),
+            &key(other_key),
+            &matcher("-"),
+            other.as_bytes(),
+        )
+        .unwrap();
+        let mut out = Vec::new();
+        join.line(line, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test_case("1,3", "1,3", "a x\n", "a b", ""; "missing key field")]
+    #[test_case("1,3", "1,3", "a x\n", "a b c", ""; "missing key field in the joined file")]
+    #[test_case("2,1", "1,2", "X a\n", "a X", "a X a\n"; "swapped key fields")]
+    #[test_case("2-", "1-", "b c\nb\n", "a b c", "a b c\n"; "unbounded key")]
+    fn keys(key_spec: &str, other_key: &str, other: &str, line: &str, expected: &str) {
+        assert_eq!(keyed(key_spec, other_key, other, line), expected);
+    }
+
+    #[test_case("3.2"; "sub-field")]
+    #[test_case("/x/"; "content")]
+    fn positional_keys(spec: &str) {
+        assert_eq!(
+            Key::from_str(spec),
+            Err(knife::parser::Error::NotPositional)
+        );
+    }
+}
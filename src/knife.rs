@@ -1,23 +1,313 @@
+use crate::content::Content;
 use crate::matcher::{Matcher, Pattern};
 use crate::parser::{self, Error};
-use std::str::FromStr;
+use crate::tokenizer::{Delimiter, Tokenizer, Whitespace};
+use std::{collections::BTreeSet, fmt::Display, ops::Range, str::FromStr};
 
+/// The sub-fields are delimited by colons by default, like in `10.0.0.1:8080`
+pub const SUB_DELIMITER: char = ':';
+
+/// Extracts the fields selected by the pattern from the strings split by the tokenizer,
+/// by default the fields are delimited by whitespaces
 #[derive(Debug, PartialEq, Clone)]
-pub struct Knife {
+pub struct Knife<T = Whitespace> {
     matcher: Matcher,
+    /// The 0-based field and sub-field indexes, sorted, only for the fields not selected as a whole
+    subs: Vec<(usize, usize)>,
+    sub_delimiter: char,
+    /// Select the fields by their content
+    content: Vec<Content>,
+    tokenizer: T,
 }
 
 impl Knife {
-    fn new(pattern: Vec<Pattern>) -> Self {
-        let matcher = Matcher::new(pattern);
-        Self { matcher }
+    /// Create new `Knife` from the patterns using 0-based indexes, see `parser::from_str`
+    /// for creating them from the 1-based pattern language
+    pub fn new(pattern: Vec<Pattern>) -> Self {
+        let (subs, whole): (Vec<Pattern>, Vec<Pattern>) = pattern
+            .into_iter()
+            .partition(|x| matches!(x, Pattern::Sub(..)));
+        let subs = subs
+            .into_iter()
+            .filter_map(|x| match x {
+                Pattern::Sub(field, sub) => Some((field, sub)),
+                _ => None,
+            })
+            .collect();
+        Self {
+            matcher: Matcher::from_positional(whole),
+            subs: Vec::new(),
+            sub_delimiter: SUB_DELIMITER,
+            content: Vec::new(),
+            tokenizer: Whitespace,
+        }
+        .with_subs(subs)
+    }
+}
+
+impl<T: Tokenizer> Knife<T> {
+    /// Use the `tokenizer` for splitting the strings into fields
+    pub fn with_tokenizer<U: Tokenizer>(self, tokenizer: U) -> Knife<U> {
+        Knife {
+            matcher: self.matcher,
+            subs: self.subs,
+            sub_delimiter: self.sub_delimiter,
+            content: self.content,
+            tokenizer,
+        }
+    }
+
+    /// Use the `delimiter` for splitting the fields into sub-fields, by default [`SUB_DELIMITER`]
+    pub fn with_sub_delimiter(self, delimiter: char) -> Self {
+        Self {
+            sub_delimiter: delimiter,
+            ..self
+        }
+    }
+
+    /// Also select the fields by their `content`, see [`Content`], any field can match,
+    /// so with the content the whole line is tokenized, even past the last selected field
+    pub fn with_content(self, content: Vec<Content>) -> Self {
+        Self { content, ..self }
     }
 
     /// Extract specific fields from a string
     #[inline]
     pub fn extract<'a>(&self, string: &'a str) -> Vec<&'a str> {
-        let chunks = string.split_whitespace();
-        self.matcher.clone().iter(chunks).collect()
+        if self.is_mixed() {
+            return self
+                .extract_mixed(string)
+                .into_iter()
+                .map(|(_, _, x)| x)
+                .collect();
+        }
+        let chunks = self.tokenizer.tokens(string);
+        self.matcher.iter(chunks).collect()
+    }
+
+    /// Extract specific fields from a string together with their 1-based field indexes
+    /// and the byte ranges they occupy in the string
+    #[inline]
+    pub fn extract_spans<'a>(&self, string: &'a str) -> Vec<(usize, Range<usize>, &'a str)> {
+        if self.is_mixed() {
+            return self.extract_mixed(string);
+        }
+        let spans = self
+            .tokenizer
+            .spans(string)
+            .enumerate()
+            .map(|(index, (span, field))| (index + 1, span, field));
+        self.matcher.iter(spans).collect()
+    }
+
+    /// Extract the fields when some of them are selected by their content, or split into
+    /// the sub-fields, the sub-fields are returned in the place of the field, with its index,
+    /// any field can match the content, so the content patterns tokenize the whole line
+    fn extract_mixed<'a>(&self, string: &'a str) -> Vec<(usize, Range<usize>, &'a str)> {
+        let bound = if self.content.is_empty() {
+            let whole = self.matcher.patterns().last().map_or(0, |x| x.max());
+            self.subs.last().map_or(0, |x| x.0).max(whole)
+        } else {
+            usize::MAX
+        };
+        // the fields after the current one, selected by the content of the fields before them
+        let mut following = BTreeSet::new();
+        let mut fields = Vec::new();
+        let spans = self.tokenizer.spans(string).enumerate();
+        for (index, (span, field)) in spans.take_while(|(index, _)| *index <= bound) {
+            let mut selected = following.remove(&index) || self.matcher.contains(index);
+            for content in &self.content {
+                match content.select(index, field) {
+                    Some(next) if next > index => {
+                        following.insert(next);
+                    }
+                    Some(_) => selected = true,
+                    None => {}
+                }
+            }
+            if selected {
+                fields.push((index + 1, span, field));
+                continue;
+            }
+            let start = self.subs.partition_point(|x| x.0 < index);
+            let end = self.subs.partition_point(|x| x.0 <= index);
+            if start == end {
+                continue;
+            }
+            let subs = &self.subs[start..end];
+            for (sub, (range, value)) in Delimiter(self.sub_delimiter).spans(field).enumerate() {
+                if subs.contains(&(index, sub)) {
+                    let range = span.start + range.start..span.start + range.end;
+                    fields.push((index + 1, range, value));
+                }
+            }
+        }
+        fields
+    }
+}
+
+impl<T: Clone> Knife<T> {
+    /// Knife selecting the fields selected by any of the knives, using this knife's tokenizer
+    pub fn union<U>(&self, other: &Knife<U>) -> Self {
+        let subs = self.subs.iter().chain(&other.subs).copied().collect();
+        let mut content = self.content.clone();
+        for x in &other.content {
+            if !content.contains(x) {
+                content.push(x.clone());
+            }
+        }
+        self.with_matcher(self.matcher.union(&other.matcher), subs, content)
+    }
+
+    /// Knife selecting the fields selected by both of the knives, using this knife's tokenizer,
+    /// returns `None` if any of them selects the fields by their content, since the selected
+    /// fields then depend on the line
+    pub fn intersection<U>(&self, other: &Knife<U>) -> Option<Self> {
+        if !self.content.is_empty() || !other.content.is_empty() {
+            return None;
+        }
+        let subs = self
+            .subs
+            .iter()
+            .filter(|x| other.selects(**x))
+            .chain(other.subs.iter().filter(|x| self.matcher.contains(x.0)))
+            .copied()
+            .collect();
+        let matcher = self.matcher.intersection(&other.matcher);
+        Some(self.with_matcher(matcher, subs, Vec::new()))
+    }
+
+    /// Knife selecting the fields selected by this knife, but not by the `other`,
+    /// the fields selected as a whole stay whole, even if the `other` selects their sub-fields,
+    /// returns `None` if any of the knives selects the fields by their content
+    pub fn difference<U>(&self, other: &Knife<U>) -> Option<Self> {
+        if !self.content.is_empty() || !other.content.is_empty() {
+            return None;
+        }
+        let subs = self
+            .subs
+            .iter()
+            .filter(|x| !other.selects(**x))
+            .copied()
+            .collect();
+        let matcher = self.matcher.difference(&other.matcher);
+        Some(self.with_matcher(matcher, subs, Vec::new()))
+    }
+
+    fn with_matcher(
+        &self,
+        matcher: Matcher,
+        subs: Vec<(usize, usize)>,
+        content: Vec<Content>,
+    ) -> Self {
+        Self {
+            matcher,
+            subs: Vec::new(),
+            sub_delimiter: self.sub_delimiter,
+            content,
+            tokenizer: self.tokenizer.clone(),
+        }
+        .with_subs(subs)
+    }
+}
+
+impl<T> Knife<T> {
+    /// Set the sub-fields, skipping the ones of the fields selected as a whole
+    fn with_subs(mut self, mut subs: Vec<(usize, usize)>) -> Self {
+        subs.retain(|x| !self.matcher.contains(x.0));
+        subs.sort_unstable();
+        subs.dedup();
+        self.subs = subs;
+        self
+    }
+
+    /// Check if the 0-based sub-field is selected, by itself or with the whole field
+    fn selects(&self, sub: (usize, usize)) -> bool {
+        self.matcher.contains(sub.0) || self.subs.binary_search(&sub).is_ok()
+    }
+
+    /// Check if the fields are selected by anything else than their positions
+    fn is_mixed(&self) -> bool {
+        !self.subs.is_empty() || !self.content.is_empty()
+    }
+
+    /// Check if the knife selects no fields
+    pub fn is_empty(&self) -> bool {
+        self.matcher.is_empty() && self.subs.is_empty() && self.content.is_empty()
+    }
+
+    /// Check if the 1-based `field` is selected by its position, as a whole or any of its sub-fields,
+    /// unlike [`Matcher::contains`], which takes the 0-based indexes
+    pub fn contains_field(&self, field: usize) -> bool {
+        field > 0
+            && (self.matcher.contains(field - 1) || self.subs.iter().any(|x| x.0 == field - 1))
+    }
+
+    /// Iterate over the selected 1-based fields in increasing order, including the ones with
+    /// the selected sub-fields, returns `None` if the pattern is not bounded, or if the fields
+    /// are selected by their content
+    pub fn fields(&self) -> Option<impl Iterator<Item = usize> + '_> {
+        if !self.content.is_empty() {
+            return None;
+        }
+        let mut fields: Vec<usize> = self
+            .matcher
+            .indices()?
+            .chain(self.subs.iter().map(|x| x.0))
+            .collect();
+        fields.sort_unstable();
+        fields.dedup();
+        Some(fields.into_iter().map(|index| index + 1))
+    }
+
+    /// The normalized patterns, including the sub-fields, sorted by the fields
+    pub fn patterns(&self) -> Vec<Pattern> {
+        let mut patterns = self.matcher.patterns().to_vec();
+        patterns.extend(
+            self.subs
+                .iter()
+                .map(|&(field, sub)| Pattern::Sub(field, sub)),
+        );
+        patterns.sort_by_key(|x| x.min());
+        patterns
+    }
+
+    /// The patterns selecting the fields by their content
+    pub fn content(&self) -> &[Content] {
+        &self.content
+    }
+
+    /// The matcher used for selecting the fields as a whole, the fields with only
+    /// the sub-fields selected are not included
+    pub fn matcher(&self) -> &Matcher {
+        &self.matcher
+    }
+
+    /// The tokenizer used for splitting the strings into fields
+    pub fn tokenizer(&self) -> &T {
+        &self.tokenizer
+    }
+
+    /// The delimiter used for splitting the fields into sub-fields
+    pub fn sub_delimiter(&self) -> char {
+        self.sub_delimiter
+    }
+}
+
+/// Display the normalized pattern using the 1-based pattern language
+///
+/// Like with [`Matcher`], the empty knife is displayed as the empty string, which does not parse.
+impl<T> Display for Knife<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let patterns = self.patterns().into_iter().map(|x| x.to_string());
+        let content = self.content.iter().map(|x| x.to_string());
+        for (i, pattern) in patterns.chain(content).enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}", pattern)?;
+        }
+        Ok(())
     }
 }
 
@@ -25,14 +315,16 @@ impl FromStr for Knife {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Self::new(parser::from_str(s)?))
+        let (patterns, content) = parser::with_content(s)?;
+        Ok(Self::new(patterns).with_content(content))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::Knife;
-    use std::str::FromStr;
+    use crate::tokenizer::{Delimiter, Tokenizer};
+    use std::{ops::Range, str::FromStr};
     use test_case::test_case;
 
     #[test_case("1", "Mary had a little lamb.", &["Mary"]; "single field exists")]
@@ -45,4 +337,193 @@ mod tests {
         let knife = Knife::from_str(spec).unwrap();
         assert_eq!(knife.extract(example), expected);
     }
+
+    #[test_case("1", " Mary had a little lamb.", &[(1, 1..5, "Mary")]; "single field")]
+    #[test_case("10", "Mary had a little lamb.", &[]; "field does not exist")]
+    #[test_case("2, 4-", "Mary  had a\tlittle lamb.", &[(2, 6..9, "had"), (4, 12..18, "little"), (5, 19..24, "lamb.")]; "mixed")]
+    fn extract_spans(spec: &str, example: &str, expected: &[(usize, Range<usize>, &str)]) {
+        let knife = Knife::from_str(spec).unwrap();
+        assert_eq!(knife.extract_spans(example), expected);
+    }
+
+    #[test_case("2", "a,b,c", &["b"]; "single field")]
+    #[test_case("2-", "a,,c", &["", "c"]; "empty field")]
+    #[test_case("1,3", "a b,c", &["a b"]; "whitespaces are not delimiters")]
+    fn extract_with_tokenizer(spec: &str, example: &str, expected: &[&str]) {
+        let knife = Knife::from_str(spec)
+            .unwrap()
+            .with_tokenizer(Delimiter(','));
+        assert_eq!(knife.extract(example), expected);
+    }
+
+    #[test]
+    fn custom_tokenizer() {
+        struct Chars;
+
+        impl Tokenizer for Chars {
+            fn spans<'a, 'b>(
+                &'a self,
+                string: &'b str,
+            ) -> Box<dyn Iterator<Item = (Range<usize>, &'b str)> + 'a>
+            where
+                'b: 'a,
+            {
+                Box::new(
+                    string
+                        .char_indices()
+                        .map(|(i, c)| (i..i + c.len_utf8(), &string[i..i + c.len_utf8()])),
+                )
+            }
+        }
+
+        let knife = Knife::from_str("2,4-").unwrap().with_tokenizer(Chars);
+        assert_eq!(knife.extract("abcde"), ["b", "d", "e"]);
+        assert_eq!(knife.extract_spans("ółw"), [(2, 2..4, "ł")]);
+    }
+
+    #[test_case("1-3", "2-5", "1-5"; "union")]
+    #[test_case("1,3", "2", "1-3"; "union merges adjacent")]
+    #[test_case("1-3, 9", "7-", "1-3,7-"; "union with unbounded")]
+    fn union(lhs: &str, rhs: &str, expected: &str) {
+        let (lhs, rhs) = (Knife::from_str(lhs).unwrap(), Knife::from_str(rhs).unwrap());
+        assert_eq!(lhs.union(&rhs).to_string(), expected);
+    }
+
+    #[test_case("1-3", "2-5", "2-3"; "intersection")]
+    #[test_case("1,3", "2", ""; "disjoint")]
+    #[test_case("1-5, 7-", "3, 5-8, 10", "3,5,7-8,10"; "multiple")]
+    fn intersection(lhs: &str, rhs: &str, expected: &str) {
+        let (lhs, rhs) = (Knife::from_str(lhs).unwrap(), Knife::from_str(rhs).unwrap());
+        assert_eq!(lhs.intersection(&rhs).unwrap().to_string(), expected);
+    }
+
+    #[test_case("1-5", "2-3", "1,4-5"; "hole in the middle")]
+    #[test_case("1-5", "1-10", ""; "nothing left")]
+    #[test_case("-", "2, 4-5, 9-", "1,3,6-8"; "unbounded")]
+    #[test_case("1-3, 7", "3-7", "1-2"; "overlapping both")]
+    fn difference(lhs: &str, rhs: &str, expected: &str) {
+        let (lhs, rhs) = (Knife::from_str(lhs).unwrap(), Knife::from_str(rhs).unwrap());
+        assert_eq!(lhs.difference(&rhs).unwrap().to_string(), expected);
+    }
+
+    #[test]
+    fn set_algebra_keeps_tokenizer() {
+        let base = Knife::from_str("1-3")
+            .unwrap()
+            .with_tokenizer(Delimiter(','));
+        let extra = Knife::from_str("5").unwrap();
+        assert_eq!(
+            base.union(&extra).extract("a,b,c,d,e"),
+            ["a", "b", "c", "e"]
+        );
+    }
+
+    #[test_case("3.2", "a b 10.0.0.1:8080", &[(3, 13..17, "8080")]; "sub-field")]
+    #[test_case("1, 3.2, 3.1", "a b c:d", &[(1, 0..1, "a"), (3, 4..5, "c"), (3, 6..7, "d")]; "in order")]
+    #[test_case("3, 3.2", "a b c:d", &[(3, 4..7, "c:d")]; "whole field")]
+    #[test_case("2.3, 5", "a b:c d", &[]; "sub-field does not exist")]
+    #[test_case("1.1", "a b", &[(1, 0..1, "a")]; "no delimiter")]
+    fn extract_subs(spec: &str, example: &str, expected: &[(usize, Range<usize>, &str)]) {
+        let knife = Knife::from_str(spec).unwrap();
+        assert_eq!(knife.extract_spans(example), expected);
+        let fields: Vec<&str> = expected.iter().map(|x| x.2).collect();
+        assert_eq!(knife.extract(example), fields);
+    }
+
+    #[test]
+    fn sub_delimiter() {
+        let knife = Knife::from_str("2.2, 3")
+            .unwrap()
+            .with_tokenizer(Delimiter(','))
+            .with_sub_delimiter('=');
+        assert_eq!(knife.extract("a,user=mary,c"), ["mary", "c"]);
+        assert_eq!(knife.sub_delimiter(), '=');
+    }
+
+    #[test_case("3.2, 1, 3.1, 2.1, 2", "1-2,3.1,3.2"; "normalized")]
+    fn display_subs(spec: &str, expected: &str) {
+        let knife = Knife::from_str(spec).unwrap();
+        assert_eq!(knife.to_string(), expected);
+        assert_eq!(Knife::from_str(expected).unwrap(), knife);
+    }
+
+    #[test_case("3.1", "3.2", "3.1,3.2"; "union")]
+    #[test_case("3.1", "3", "3"; "union with whole field")]
+    fn union_subs(lhs: &str, rhs: &str, expected: &str) {
+        let (lhs, rhs) = (Knife::from_str(lhs).unwrap(), Knife::from_str(rhs).unwrap());
+        assert_eq!(lhs.union(&rhs).to_string(), expected);
+    }
+
+    #[test_case("3.1, 3.2", "3.2", "3.2"; "intersection")]
+    #[test_case("3", "3.2, 4", "3.2"; "intersection with whole field")]
+    fn intersection_subs(lhs: &str, rhs: &str, expected: &str) {
+        let (lhs, rhs) = (Knife::from_str(lhs).unwrap(), Knife::from_str(rhs).unwrap());
+        assert_eq!(lhs.intersection(&rhs).unwrap().to_string(), expected);
+    }
+
+    #[test_case("1, 3.1, 3.2", "3.2", "1,3.1"; "difference")]
+    #[test_case("3.1", "1-5", ""; "difference with whole field")]
+    #[test_case("3", "3.1", "3"; "whole field stays")]
+    fn difference_subs(lhs: &str, rhs: &str, expected: &str) {
+        let (lhs, rhs) = (Knife::from_str(lhs).unwrap(), Knife::from_str(rhs).unwrap());
+        assert_eq!(lhs.difference(&rhs).unwrap().to_string(), expected);
+    }
+
+    #[test_case("/^user:/", "id:1 user:mary ok", &[(2, 5..14, "user:mary")]; "matching")]
+    #[test_case("/^-p$/+", "ssh -p 22 host", &[(3, 7..9, "22")]; "next field")]
+    #[test_case("/^-p$/+2", "ssh -p 22 host", &[(4, 10..14, "host")]; "further field")]
+    #[test_case("/^-p$/+", "ssh host -p", &[]; "no next field")]
+    #[test_case("1, /^-p$/+, 4.2", "ssh -p 22 a:b", &[(1, 0..3, "ssh"), (3, 7..9, "22"), (4, 12..13, "b")]; "mixed")]
+    #[test_case("/a/, /b/", "ab c", &[(1, 0..2, "ab")]; "selected twice")]
+    #[test_case("/x/", "a b", &[]; "no match")]
+    fn extract_content(spec: &str, example: &str, expected: &[(usize, Range<usize>, &str)]) {
+        let knife = Knife::from_str(spec).unwrap();
+        assert_eq!(knife.extract_spans(example), expected);
+        let fields: Vec<&str> = expected.iter().map(|x| x.2).collect();
+        assert_eq!(knife.extract(example), fields);
+    }
+
+    #[test_case("/x/+, 3, /a\\/b/", "3,/x/+,/a\\/b/"; "display")]
+    fn display_content(spec: &str, expected: &str) {
+        let knife = Knife::from_str(spec).unwrap();
+        assert_eq!(knife.to_string(), expected);
+        assert_eq!(Knife::from_str(expected).unwrap(), knife);
+    }
+
+    #[test]
+    fn set_algebra_with_content() {
+        let lhs = Knife::from_str("1, /a/, /b/").unwrap();
+        let rhs = Knife::from_str("2, /b/, /c/").unwrap();
+        assert_eq!(lhs.union(&rhs).to_string(), "1-2,/a/,/b/,/c/");
+        assert_eq!(lhs.intersection(&rhs), None);
+        assert_eq!(lhs.difference(&rhs), None);
+        let positional = Knife::from_str("1-3").unwrap();
+        assert_eq!(positional.intersection(&rhs), None);
+        assert_eq!(positional.difference(&rhs), None);
+        assert!(lhs.fields().is_none());
+        assert!(!Knife::from_str("/a/").unwrap().is_empty());
+    }
+
+    #[test]
+    fn contains_and_fields() {
+        let knife = Knife::from_str("2-4, 7").unwrap();
+        assert!(!knife.is_empty());
+        assert!(knife.contains_field(7));
+        assert!(knife.contains_field(2));
+        assert!(!knife.contains_field(1));
+        assert!(!knife.contains_field(0));
+        assert_eq!(knife.fields().unwrap().collect::<Vec<_>>(), [2, 3, 4, 7]);
+
+        let unbounded = Knife::from_str("3-").unwrap();
+        assert!(unbounded.contains_field(100));
+        assert!(unbounded.fields().is_none());
+
+        assert!(knife.difference(&knife).unwrap().is_empty());
+
+        let subs = Knife::from_str("5.1, 2").unwrap();
+        assert!(subs.contains_field(5));
+        assert!(!subs.contains_field(4));
+        assert_eq!(subs.fields().unwrap().collect::<Vec<_>>(), [2, 5]);
+        assert!(!subs.is_empty());
+    }
 }
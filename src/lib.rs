@@ -0,0 +1,50 @@
+//! It's just a sharper knife.
+//!
+//! Extract fields from strings, where the fields are delimited by [any whitespaces], and selected
+//! using the same pattern language as the `knife` command line tool.
+//!
+//! ```
+//! use knife::Knife;
+//! use std::str::FromStr;
+//!
+//! let knife = Knife::from_str("1,3-4").unwrap();
+//! assert_eq!(knife.extract("Mary had a little lamb."), ["Mary", "a", "little"]);
+//! ```
+//!
+//! The fields can be also delimited in other ways, by passing a different [`Tokenizer`].
+//!
+//! ```
+//! use knife::{tokenizer::Delimiter, Knife};
+//! use std::str::FromStr;
+//!
+//! let knife = Knife::from_str("2-").unwrap().with_tokenizer(Delimiter(','));
+//! assert_eq!(knife.extract("a,b,,c"), ["b", "", "c"]);
+//! ```
+//!
+//! The fields can be split further into sub-fields, selected like `3.2`, by default they are
+//! delimited by the [`SUB_DELIMITER`].
+//!
+//! ```
+//! use knife::Knife;
+//! use std::str::FromStr;
+//!
+//! let knife = Knife::from_str("1,3.2").unwrap();
+//! assert_eq!(knife.extract("GET /index.html 10.0.0.1:8080"), ["GET", "8080"]);
+//! ```
+//!
+//! [any whitespaces]: https://doc.rust-lang.org/std/str/struct.SplitWhitespace.html
+
+pub mod content;
+pub mod explain;
+pub mod ffi;
+mod knife;
+pub mod logfmt;
+pub mod matcher;
+pub mod parser;
+#[cfg(feature = "serde")]
+pub mod serialize;
+pub mod tokenizer;
+
+pub use crate::knife::{Knife, SUB_DELIMITER};
+pub use matcher::{Filter, Matcher, Pattern};
+pub use tokenizer::Tokenizer;
@@ -0,0 +1,181 @@
+//! Selecting the values from the `key=value` lines, like `level=info msg="all good" user=alice`.
+
+use crate::{
+    parser::{self, Error},
+    tokenizer::{Quoted, Tokenizer},
+    Knife,
+};
+use std::str::FromStr;
+
+/// Extracts the values by their keys, and the fields by their positions, from the lines
+/// of `key=value` pairs, the quoted values can contain whitespaces
+///
+/// ```
+/// use knife::logfmt::Logfmt;
+/// use std::str::FromStr;
+///
+/// let logfmt = Logfmt::from_str("1, user").unwrap();
+/// let line = r#"time=12:00 level=info msg="logged in" user=alice"#;
+/// assert_eq!(logfmt.extract(line), ["time=12:00", "alice"]);
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct Logfmt {
+    knife: Knife<Quoted>,
+    keys: Vec<String>,
+}
+
+impl Logfmt {
+    /// Extract the values of the selected keys and the selected fields, in the order
+    /// they appear in the line
+    pub fn extract<'a>(&self, line: &'a str) -> Vec<&'a str> {
+        let fields = self.knife.extract_spans(line);
+        if self.keys.is_empty() {
+            return fields.into_iter().map(|(_, _, field)| field).collect();
+        }
+
+        let values = Quoted
+            .tokens(line)
+            .enumerate()
+            .filter_map(|(index, field)| {
+                let (key, value) = field.split_once('=')?;
+                if self.keys.iter().any(|x| x == key) {
+                    Some((index + 1, unquote(value)))
+                } else {
+                    None
+                }
+            });
+
+        // merge both, they are ordered by the field numbers
+        let mut fields = fields
+            .into_iter()
+            .map(|(number, _, field)| (number, field))
+            .peekable();
+        let mut extracted = Vec::new();
+        for (number, value) in values {
+            while let Some((_, field)) = fields.next_if(|x| x.0 <= number) {
+                extracted.push(field);
+            }
+            extracted.push(value);
+        }
+        extracted.extend(fields.map(|(_, field)| field));
+        extracted
+    }
+
+    /// Use the `delimiter` for splitting the fields into the sub-fields, see
+    /// [`Knife::with_sub_delimiter`]
+    pub fn with_sub_delimiter(self, delimiter: char) -> Self {
+        Self {
+            knife: self.knife.with_sub_delimiter(delimiter),
+            ..self
+        }
+    }
+
+    /// The keys of the selected values
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    /// The knife selecting the fields by their positions
+    pub fn knife(&self) -> &Knife<Quoted> {
+        &self.knife
+    }
+}
+
+/// Parse the comma-separated keys mixed with the patterns of the pattern language, like
+/// `1, level, msg`, the keys start with a letter or `_`, and can contain letters, digits,
+/// `_`, `-`, and `.`
+impl FromStr for Logfmt {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut keys = Vec::new();
+        let mut positions = Vec::new();
+        for part in s.split(',').map(str::trim).filter(|x| !x.is_empty()) {
+            if is_key(part) {
+                keys.push(part.to_string());
+            } else {
+                positions.push(part);
+            }
+        }
+
+        let patterns = match (positions.is_empty(), keys.is_empty()) {
+            (true, true) => return Err(Error::Empty),
+            (true, false) => Vec::new(),
+            (false, _) => parser::from_str(&positions.join(","))?,
+        };
+        Ok(Self {
+            knife: Knife::new(patterns).with_tokenizer(Quoted),
+            keys,
+        })
+    }
+}
+
+/// Check if the string can be a key, not a pattern
+fn is_key(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+/// Strip the quotes surrounding the value
+fn unquote(value: &str) -> &str {
+    for q in ['"', '\''] {
+        if value.len() > 1 && value.starts_with(q) && value.ends_with(q) {
+            return &value[1..value.len() - 1];
+        }
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Logfmt;
+    use crate::parser::Error;
+    use std::str::FromStr;
+    use test_case::test_case;
+
+    const LINE: &str = r#"level=info msg="user logged in" user=alice took=12ms"#;
+
+    #[test_case("user", &["alice"]; "single key")]
+    #[test_case("user, level", &["info", "alice"]; "in order of the line")]
+    #[test_case("msg", &["user logged in"]; "quoted value")]
+    #[test_case("2", &[r#"msg="user logged in""#]; "position")]
+    #[test_case("took, 1", &["level=info", "12ms"]; "mixed")]
+    #[test_case("3, user", &["user=alice", "alice"]; "same field twice")]
+    #[test_case("missing, 9", &[]; "nothing selected")]
+    fn extract(spec: &str, expected: &[&str]) {
+        let logfmt = Logfmt::from_str(spec).unwrap();
+        assert_eq!(logfmt.extract(LINE), expected);
+    }
+
+    #[test]
+    fn single_quotes_and_empty_values() {
+        let logfmt = Logfmt::from_str("a,b,c").unwrap();
+        assert_eq!(logfmt.extract("a='x y' b= c"), ["x y", ""]);
+    }
+
+    #[test]
+    fn apostrophe_in_value() {
+        let logfmt = Logfmt::from_str("level").unwrap();
+        assert_eq!(logfmt.extract("msg=it's level=info"), ["info"]);
+    }
+
+    #[test]
+    fn sub_delimiter() {
+        let logfmt = Logfmt::from_str("2.2, a").unwrap().with_sub_delimiter('=');
+        assert_eq!(logfmt.extract("a=x k=v=w"), ["x", "v"]);
+    }
+
+    #[test_case("user, 1-2, http.status", &["user", "http.status"]; "keys")]
+    #[test_case("3.2", &[]; "sub-field is not a key")]
+    fn keys(spec: &str, expected: &[&str]) {
+        assert_eq!(Logfmt::from_str(spec).unwrap().keys(), expected);
+    }
+
+    #[test_case("", Error::Empty; "empty")]
+    #[test_case("user, 0", Error::StartsAtOne; "invalid position")]
+    #[test_case("user, 1$", Error::CannotParse; "invalid key")]
+    fn invalid(spec: &str, expected: Error) {
+        assert_eq!(Logfmt::from_str(spec).unwrap_err(), expected);
+    }
+}
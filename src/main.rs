@@ -1,95 +1,910 @@
-mod knife;
-mod matcher;
-mod parser;
+mod config;
+mod count;
+mod follow;
+mod join;
+mod paragraph;
+mod preview;
+mod process;
+mod split;
+mod stats;
+mod summary;
+mod transpose;
+mod unique;
 
-use clap::Parser;
-use knife::Knife;
+use clap::{error::ErrorKind, CommandFactory, Parser};
+use count::Count;
+use follow::Follower;
+use join::Join;
+use knife::{
+    logfmt::Logfmt,
+    parser,
+    tokenizer::{Delimiter, Whitespace},
+    Knife, Matcher, Pattern, Tokenizer,
+};
+use paragraph::Paragraphs;
+use preview::Preview;
+use process::{Cutter, Extract, ExtractPairs, Process};
+use split::Split;
+use stats::Stats;
 use std::{
-    fs::File,
-    io::{self, BufRead, BufReader, Read, Write},
-    path::PathBuf,
+    ffi::OsString,
+    fmt::Display,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, BufWriter, IsTerminal, Read, Write},
+    path::{Path, PathBuf},
+    process::exit,
+    str::FromStr,
+    thread,
+    time::Duration,
 };
+use summary::Summary;
+use transpose::Transpose;
 use unescape::unescape;
+use unique::Unique;
 
 const DETAILS: &str = color_print::cstr!(
     "<u><s>Details:</s></u>
 
 The <<FIELDS>> are specified using a pattern language where N stands for for N-th field (starting at 1), \
 -N for all the fields up to N-th (inclusive), N- for all the fields starting from N-th (inclusive), \
-N-M for a closed range, and comma-separated list for a combination of the patterns. \
-It is also possible to use : instead of - for defining ranges.
+N-M for a closed range, N.M for the M-th sub-field of the N-th field, and comma-separated list for a combination \
+of the patterns. It is also possible to use : instead of - for defining ranges. The sub-fields are delimited \
+by colons, so 3.2 selects the port from 10.0.0.1:8080, unless --sub-delimiter is given.
+The fields can also be selected by their content, /REGEX/ selects the fields matching the regular expression, \
+and /REGEX/+N the N-th field after them (the next one if N is omitted), so /^-p$/+ selects the port of ssh -p 22.
+With --paragraph, the <<FIELDS>> are selected from the records spanning multiple lines, like the blocks \
+printed by ip addr, instead of the lines.
+With --logfmt, the <<FIELDS>> can also include the keys, like level,msg, that select the values of the key=value \
+pairs.
+
+The <<FIELDS>> can also be @NAME, the name of the alias saved in the config file, which is read from $KNIFE_CONFIG, \
+or knife/config.toml in $XDG_CONFIG_HOME, or ~/.config. The aliases can be saved as NAME = \"FIELDS\", \
+or as tables with the options (delimiter, sub-delimiter, output-separator) that are used unless given \
+in the command line:
+
+  ps = \"2,11-\"
+
+  [passwd]
+  fields = \"1,6\"
+  delimiter = \":\"
+  output-separator = \"\\t\"
 
 The extracted fields are printed in the order they appeared in the input. If no fields were extracted, nothing is \
-printed.");
+printed.
+
+<u><s>Exit status:</s></u>
+
+  0    fields were extracted
+  1    no fields were extracted
+  2    invalid arguments or <<FIELDS>>
+  3    some of the files could not be read, or the output could not be written
+  141  the output pipe was closed");
 
 /// Like the cut command, but delimits fields with whitespaces.
 #[derive(Parser, Debug)]
 #[command(after_long_help = DETAILS)]
 struct Args {
-    /// Join the output fields with the separator. The escaped unicode characters like \t are allowed.
-    /// If in doubt, the separator would be used as-is.
+    /// Join the output fields with the separator, by default with a space. The escaped unicode
+    /// characters like \t are allowed. If in doubt, the separator would be used as-is.
+    #[arg(short, long, value_name = "STRING", allow_hyphen_values = true)]
+    output_separator: Option<String>,
+
+    /// Delimit the fields with the character instead of whitespaces, like in the cut command,
+    /// so consecutive delimiters mark empty fields. The escaped unicode characters like \t are allowed.
+    #[arg(short, long, value_name = "CHAR", allow_hyphen_values = true)]
+    delimiter: Option<String>,
+
+    /// Delimit the sub-fields, selected like 3.2, with the character, by default with a colon.
+    /// The escaped unicode characters like \t are allowed.
+    #[arg(long, value_name = "CHAR", allow_hyphen_values = true)]
+    sub_delimiter: Option<String>,
+
+    /// Flush the output after every line, useful for interactive pipelines like `tail -f`.
+    /// By default the output is block-buffered.
+    #[arg(long)]
+    line_buffered: bool,
+
+    /// Keep reading the lines appended to the files, like tail -F, the files are followed by name,
+    /// so they are opened again when rotated, and read from the start when truncated.
+    /// The output is line-buffered. It cannot be used with the modes printing only at the end.
     #[arg(
         short,
+        long,
+        conflicts_with_all = ["in_place", "stats", "summary", "count", "transpose"]
+    )]
+    follow: bool,
+
+    /// Edit the files in place, if the SUFFIX is given, e.g. -i.bak, keep the original files
+    /// as backups with the suffix appended to their names.
+    #[arg(
+        short = 'i',
+        long = "in-place",
+        value_name = "SUFFIX",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = ""
+    )]
+    in_place: Option<String>,
+
+    /// Instead of extracting the fields, print the first N lines (10 by default) with the fields
+    /// annotated with their numbers, like [1]Mary [2]had, use - as <FIELDS> to select all of them.
+    #[arg(
+        long,
+        visible_alias = "number",
+        value_name = "N",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "10",
+        conflicts_with_all = ["in_place", "follow"]
+    )]
+    preview: Option<usize>,
+
+    /// When previewing, also show the field numbers counted from the end, like [1|-5]Mary.
+    #[arg(long, requires = "preview")]
+    negative: bool,
+
+    /// Write the extracted fields to the files named after the value of the FIELD-th field,
+    /// like awk '{print > $3}', instead of printing them. The lines without it are skipped.
+    #[arg(
+        long,
+        value_name = "FIELD",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+        conflicts_with_all = ["in_place", "preview"]
+    )]
+    split_by: Option<usize>,
+
+    /// Path of the files when splitting, where {} is replaced by the value of the key field,
+    /// like logs/{}/access.log, the directories are created as needed. The existing files
+    /// are overwritten.
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        default_value = split::PLACEHOLDER,
+        requires = "split_by"
+    )]
+    split_path: String,
+
+    /// Keep at most N files open when splitting, the least recently used one is closed
+    /// when more are needed, and appended to when opened again.
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 64,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+        requires = "split_by"
+    )]
+    max_open: usize,
+
+    /// Join the lines with the lines of the FILE having the same key, the fields selected
+    /// by --join-fields are printed after the <FIELDS>. The FILE is read into memory,
+    /// so neither input needs to be sorted.
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["in_place", "preview", "split_by"])]
+    join: Option<PathBuf>,
+
+    /// Which lines are printed when joining.
+    #[arg(
+        long,
+        value_name = "KIND",
+        value_enum,
+        default_value_t = join::Kind::Inner,
+        requires = "join"
+    )]
+    join_type: join::Kind,
+
+    /// The key fields of the lines, like 1 or 1,3, when joining. The key fields are compared
+    /// in the given order, so --key 2,1 --join-key 1,2 matches the swapped columns, the lines
+    /// lacking any of the key fields are never matched.
+    #[arg(
+        long,
+        value_name = "FIELDS",
+        default_value = "1",
+        value_parser = join::Key::from_str,
+        requires = "join"
+    )]
+    key: join::Key,
+
+    /// The key fields of the lines of the joined file, by default the same as --key.
+    #[arg(
+        long,
+        value_name = "FIELDS",
+        value_parser = join::Key::from_str,
+        requires = "join"
+    )]
+    join_key: Option<join::Key>,
+
+    /// The fields of the joined file printed after the <FIELDS>, by default all of them.
+    #[arg(
+        long,
+        value_name = "FIELDS",
+        default_value = "-",
+        value_parser = Matcher::from_str,
+        requires = "join"
+    )]
+    join_fields: Matcher,
+
+    /// Instead of extracting the fields, report how many fields the lines of each input have,
+    /// and the numbers of the lines that differ from the most common one, use - as <FIELDS>.
+    #[arg(long, conflicts_with_all = ["in_place", "preview", "split_by", "join"])]
+    stats: bool,
+
+    /// Instead of extracting the fields, print the count, sum, min, max, and mean of each of them
+    /// over all the lines, the non-numeric values are skipped and counted.
+    #[arg(long, conflicts_with_all = ["in_place", "preview", "split_by", "join", "stats"])]
+    summary: bool,
+
+    /// Also print the quantiles, given as percents, like 50,90,99, when summarizing.
+    #[arg(
+        long,
+        value_name = "PERCENTS",
+        value_delimiter = ',',
+        value_parser = summary::parse_percent,
+        requires = "summary"
+    )]
+    quantiles: Vec<f64>,
+
+    /// Instead of extracting the fields, count the lines with the same fields, and print
+    /// the counts followed by the fields, the most common first, like sort | uniq -c | sort -rn.
+    #[arg(
+        long,
+        conflicts_with_all = ["in_place", "preview", "split_by", "join", "stats", "summary"]
+    )]
+    count: bool,
+
+    /// Print only the K most common fields when counting.
+    #[arg(long, value_name = "K", requires = "count")]
+    top: Option<usize>,
+
+    /// Print the least common fields first when counting.
+    #[arg(long, requires = "count")]
+    ascending: bool,
+
+    /// Instead of extracting the fields, print them transposed, so the fields of each line
+    /// become a column, and the columns become lines.
+    #[arg(
+        long,
+        conflicts_with_all = ["in_place", "preview", "split_by", "join", "stats", "summary", "count"]
+    )]
+    transpose: bool,
+
+    /// Use the STRING in place of the missing fields of the shorter lines when transposing,
+    /// by default they are empty. The escaped unicode characters like \t are allowed.
+    #[arg(
         long,
         value_name = "STRING",
+        default_value = "",
         allow_hyphen_values = true,
-        default_value = " "
+        requires = "transpose"
+    )]
+    fill: String,
+
+    /// Do not print the same fields again, either all the repeated ones, or only
+    /// the adjacent ones, like the uniq command.
+    #[arg(
+        long,
+        value_name = "MODE",
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "all",
+        conflicts_with_all = ["preview", "split_by", "join", "stats", "summary", "count", "transpose"]
     )]
-    output_separator: String,
+    unique: Option<unique::Mode>,
 
-    /// Select those fields, for example, 1,3-5 means fields 1, 3, 4, and 5.
-    #[arg(allow_hyphen_values = true)]
-    fields: Knife,
+    /// Remember at most N distinct rows when suppressing all the repeated ones, forgetting
+    /// the oldest, to bound the memory, so the distant repetitions can be printed, it cannot
+    /// be used with --unique=adjacent.
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+        requires = "unique"
+    )]
+    unique_limit: Option<usize>,
 
-    /// Paths to the files to process, if not given, use Stdin.
+    /// Select the values from the key=value pairs, like level=info user=alice, by their keys,
+    /// like user,level, the keys can be mixed with the fields in <FIELDS>. The values can be
+    /// quoted to contain whitespaces. The delimiter of an @NAME alias is ignored.
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "delimiter", "preview", "split_by", "join", "stats", "summary", "count", "transpose", "unique",
+            "explain"
+        ]
+    )]
+    logfmt: bool,
+
+    /// Treat the records spanning the lines up to a blank line, or up to the line equal to
+    /// the SEPARATOR, like %, as single lines, the fields are split by the whitespaces
+    /// across all the lines of the record. The delimiter of an @NAME alias is ignored.
+    /// It cannot be used with --stats, which reports the lines.
+    #[arg(
+        long,
+        value_name = "SEPARATOR",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "",
+        conflicts_with_all = ["delimiter", "follow", "stats"]
+    )]
+    paragraph: Option<String>,
+
+    /// Print the selected fields in plain words, warn about the possible mistakes in <FIELDS>,
+    /// like overlapping patterns, and exit without reading the input.
+    #[arg(long)]
+    explain: bool,
+
+    /// Select those fields, for example, 1,3-5 means fields 1, 3, 4, and 5, or @NAME of the saved alias.
+    #[arg(allow_hyphen_values = true, value_parser = parse_fields)]
+    fields: Fields,
+
+    /// Paths to the files to process, if not given, or for -, use Stdin.
     #[arg(trailing_var_arg(true))]
     file: Vec<PathBuf>,
 }
 
+/// The FIELDS argument, the pattern, the keys used with --logfmt, or the name of the alias
+#[derive(Debug, Clone)]
+enum Fields {
+    Pattern(String),
+    Keys(String),
+    Alias(String),
+}
+
+fn parse_fields(s: &str) -> Result<Fields, parser::Error> {
+    if let Some(name) = s.strip_prefix('@') {
+        return Ok(Fields::Alias(name.to_string()));
+    }
+    match Knife::from_str(s) {
+        Ok(_) => Ok(Fields::Pattern(s.to_string())),
+        Err(err @ parser::Error::InvalidRegex(_)) => Err(err),
+        // the error is more precise when the keys are skipped
+        Err(_) => Logfmt::from_str(s).map(|_| Fields::Keys(s.to_string())),
+    }
+}
+
+/// Exit with the usage error, formatted by clap
+fn usage_error(kind: ErrorKind, msg: impl Display) -> ! {
+    Args::command().error(kind, msg).exit()
+}
+
+/// Find the alias in the config file
+fn resolve(name: &str) -> config::Alias {
+    let mut aliases = config::load().unwrap_or_else(|err| usage_error(ErrorKind::Io, err));
+    aliases.remove(name).unwrap_or_else(|| {
+        usage_error(
+            ErrorKind::InvalidValue,
+            format!("alias '{}' is not defined in the config file", name),
+        )
+    })
+}
+
+/// Print the fields selected by the pattern in plain words and the possible mistakes in it
+fn explain(args: &Args) {
+    let spec = match args.fields {
+        Fields::Pattern(ref spec) | Fields::Keys(ref spec) => spec.clone(),
+        Fields::Alias(ref name) => resolve(name).fields,
+    };
+    match knife::explain::explain(&spec) {
+        Ok(explanation) => {
+            println!("{}", explanation);
+            for warning in explanation.warnings() {
+                eprintln!("warning: {}", warning);
+            }
+        }
+        Err(err) => usage_error(
+            ErrorKind::InvalidValue,
+            format!("invalid value '{}': {}", spec, err),
+        ),
+    }
+}
+
+/// Resolve the alias and create the knife, the key selection for --logfmt, and the output
+/// separator from the arguments
+fn configure(args: &Args) -> (Cutter, Option<Logfmt>, String) {
+    let (spec, delimiter, sub_delimiter, sep) = match args.fields {
+        Fields::Pattern(ref spec) | Fields::Keys(ref spec) => (spec.clone(), None, None, None),
+        Fields::Alias(ref name) => {
+            let alias = resolve(name);
+            (
+                alias.fields,
+                alias.delimiter,
+                alias.sub_delimiter,
+                alias.output_separator,
+            )
+        }
+    };
+    let invalid = |err: parser::Error| -> ! {
+        let msg = match args.fields {
+            Fields::Alias(ref name) => {
+                format!("invalid value '{}' in alias '{}': {}", spec, name, err)
+            }
+            _ => format!("invalid value '{}': {}", spec, err),
+        };
+        usage_error(ErrorKind::InvalidValue, msg)
+    };
+
+    // the records and the key=value pairs are split by the whitespaces, so the delimiter
+    // of the alias is ignored
+    let delimiter = delimiter.filter(|_| args.paragraph.is_none() && !args.logfmt);
+    let tokenizer: Box<dyn Tokenizer> = match args.delimiter.clone().or(delimiter) {
+        None => Box::new(Whitespace),
+        Some(delimiter) => Box::new(Delimiter(single_char(delimiter, "delimiter"))),
+    };
+    let sub_delimiter = match args.sub_delimiter.clone().or(sub_delimiter) {
+        None => knife::SUB_DELIMITER,
+        Some(delimiter) => single_char(delimiter, "sub-delimiter"),
+    };
+
+    let (knife, logfmt) = if args.logfmt {
+        let logfmt = Logfmt::from_str(&spec).unwrap_or_else(|err| invalid(err));
+        (
+            Knife::new(Vec::new()),
+            Some(logfmt.with_sub_delimiter(sub_delimiter)),
+        )
+    } else {
+        let knife = Knife::from_str(&spec).unwrap_or_else(|err| {
+            if Logfmt::from_str(&spec).is_ok() {
+                usage_error(
+                    ErrorKind::InvalidValue,
+                    format!(
+                        "invalid value '{}': {}, or --logfmt is needed for selecting \
+                        the values by the keys",
+                        spec, err
+                    ),
+                );
+            }
+            invalid(err)
+        });
+        (knife, None)
+    };
+
+    let sep = args
+        .output_separator
+        .clone()
+        .or(sep)
+        .unwrap_or_else(|| " ".to_string());
+    let sep = unescape(&sep).unwrap_or(sep);
+
+    let knife = knife
+        .with_tokenizer(tokenizer)
+        .with_sub_delimiter(sub_delimiter);
+    (knife, logfmt, sep)
+}
+
+/// Unescape the delimiter and check that it is a single character
+fn single_char(delimiter: String, name: &str) -> char {
+    let delimiter = unescape(&delimiter).unwrap_or(delimiter);
+    let mut chars = delimiter.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => c,
+        _ => usage_error(
+            ErrorKind::InvalidValue,
+            format!("the {} needs to be a single character", name),
+        ),
+    }
+}
+
+/// Processor printing the extracted fields
+fn extractor<'a>(
+    knife: &'a Cutter,
+    logfmt: Option<&'a Logfmt>,
+    sep: &'a str,
+    args: &Args,
+) -> Box<dyn Process + 'a> {
+    if let Some(logfmt) = logfmt {
+        return Box::new(ExtractPairs::new(logfmt, sep));
+    }
+    match args.unique {
+        Some(mode) => Box::new(Unique::new(knife, sep, mode, args.unique_limit)),
+        None => Box::new(Extract::new(knife, sep)),
+    }
+}
+
+/// Pass the records instead of the lines to the processor, if asked for
+fn paragraphs<'a>(processor: Box<dyn Process + 'a>, args: &Args) -> Box<dyn Process + 'a> {
+    match args.paragraph {
+        Some(ref separator) => Box::new(Paragraphs::new(processor, separator)),
+        None => processor,
+    }
+}
+
 type Reader = BufReader<Box<dyn Read>>;
+type Writer = Box<dyn Write>;
+
+/// Exit status used when no fields were extracted, clap uses 2 for invalid arguments
+const NO_FIELDS: i32 = 1;
+/// Exit status used when reading or writing failed
+const IO_ERROR: i32 = 3;
+/// Exit status used when the downstream pipe was closed, as if killed by SIGPIPE
+const BROKEN_PIPE: i32 = 128 + 13;
+
+/// What happened when processing the inputs, used to pick the exit status
+#[derive(Debug, Default)]
+struct Status {
+    /// Any fields were extracted
+    extracted: bool,
+    /// Any of the inputs could not be read
+    failed: bool,
+}
+
+impl Status {
+    fn exit_code(&self) -> i32 {
+        if self.failed {
+            IO_ERROR
+        } else if !self.extracted {
+            NO_FIELDS
+        } else {
+            0
+        }
+    }
+
+    /// Report the error with the input and carry on
+    fn report(&mut self, name: &str, err: io::Error) {
+        eprintln!("{}: {}", name, err);
+        self.failed = true;
+    }
+}
+
+/// Open the file for reading, `-` stands for Stdin
+fn open(path: &Path) -> io::Result<Reader> {
+    if path == Path::new("-") {
+        return Ok(BufReader::new(Box::new(io::stdin())));
+    }
+    Ok(BufReader::new(Box::new(File::open(path)?)))
+}
 
 #[inline]
-fn process_lines(reader: Reader, out: &mut io::StdoutLock, knife: &Knife, sep: &str) {
-    reader
-        .lines()
-        .filter_map(|line| {
-            match line {
-                Ok(line) => Some(line),
-                Err(err) => {
-                    // print errors to stderr and carry on
-                    eprintln!("{}", err);
-                    None
+fn process_lines(
+    reader: Reader,
+    name: &str,
+    out: &mut Writer,
+    processor: &mut dyn Process,
+    status: &mut Status,
+) -> io::Result<()> {
+    for line in reader.lines() {
+        if processor.is_done() {
+            break;
+        }
+        let line = match line {
+            Ok(line) => line,
+            Err(err) => {
+                let invalid_line = err.kind() == io::ErrorKind::InvalidData;
+                status.report(name, err);
+                if invalid_line {
+                    // skip the line and carry on
+                    processor.skip_line();
+                    continue;
                 }
+                // otherwise it could fail forever, e.g. when reading a directory
+                break;
             }
-        })
-        .for_each(|ref line| {
-            let fields = knife.extract(line).join(sep);
-            if let Err(err) = out.write_all(fields.as_bytes()) {
-                eprintln!("{}", err)
+        };
+        status.extracted |= processor.line(&line, out)?;
+    }
+    status.extracted |= processor.end_input(name, out)?;
+    Ok(())
+}
+
+/// How long to wait before checking the followed files again
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Print the fields from the lines appended to the files, never stops unless writing failed
+fn follow_files(
+    paths: &[PathBuf],
+    out: &mut Writer,
+    processor: &mut dyn Process,
+) -> io::Result<()> {
+    let mut followers: Vec<(String, Follower)> = paths
+        .iter()
+        .map(|path| (path.to_string_lossy().to_string(), Follower::new(path)))
+        .collect();
+    for (name, follower) in &followers {
+        if !follower.is_open() {
+            eprintln!("{}: cannot open the file, waiting for it to appear", name);
+        }
+    }
+
+    let mut lines = Vec::new();
+    loop {
+        let mut idle = true;
+        for (name, follower) in &mut followers {
+            if let Err(err) = follower.poll(&mut lines) {
+                eprintln!("{}: {}", name, err);
+            }
+            idle &= lines.is_empty();
+            for line in lines.drain(..) {
+                processor.line(&line, out)?;
             }
+        }
+        if idle {
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+/// Replace the file with the extracted fields, they are written to a temporary file
+/// in the same directory first, that is renamed to the file when everything succeeded
+fn edit_in_place(path: &Path, suffix: &str, processor: &mut dyn Process) -> io::Result<bool> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not a file"))?
+        .to_string_lossy();
+    let tmp = path.with_file_name(format!(".{}.knife-{}", name, std::process::id()));
+
+    let result = write_temporary(path, &tmp, processor).and_then(|extracted| {
+        if !suffix.is_empty() {
+            fs::copy(path, path.with_file_name(format!("{}{}", name, suffix)))?;
+        }
+        fs::rename(&tmp, path)?;
+        Ok(extracted)
+    });
+    if result.is_err() {
+        // the original file was not touched, just clean up
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
+
+/// Write the fields extracted from the lines of the `path` to the new `tmp` file
+fn write_temporary(path: &Path, tmp: &Path, processor: &mut dyn Process) -> io::Result<bool> {
+    let reader = BufReader::new(File::open(path)?);
+    let file = OpenOptions::new().write(true).create_new(true).open(tmp)?;
+    file.set_permissions(fs::metadata(path)?.permissions())?;
+
+    let mut out = BufWriter::new(file);
+    let mut extracted = false;
+    for line in reader.lines() {
+        // unlike when printing, any error means the file would be incomplete
+        extracted |= processor.line(&line?, &mut out)?;
+    }
+    extracted |= processor.end_input(&path.to_string_lossy(), &mut out)?;
+    processor.finish(&mut out)?;
+    out.into_inner()?.sync_all()?;
+    Ok(extracted)
+}
+
+/// Rewrite the short options with attached values, like `-iSUFFIX` or `-d,`, as the long ones,
+/// like `--in-place=SUFFIX`, otherwise clap would take them for the fields pattern, since
+/// the patterns can start with a hyphen
+///
+/// Only the arguments in the option position are rewritten, not the values of the options,
+/// like in `-o -ish`, and not the files, that follow the fields, or `--`.
+fn expand_attached(args: impl Iterator<Item = OsString>) -> Vec<OsString> {
+    let command = Args::command();
+    // the options taking the value from the next argument
+    let takes_value = |option: &str| {
+        command.get_arguments().any(|arg| {
+            let named = match option.strip_prefix("--") {
+                Some(long) => arg.get_long() == Some(long),
+                None => option.chars().nth(1) == arg.get_short() && option.len() == 2,
+            };
+            named && arg.get_action().takes_values() && !arg.is_require_equals_set()
         })
+    };
+
+    let mut args = args.peekable();
+    let mut expanded: Vec<OsString> = args.next().into_iter().collect();
+    // the fields were already given, so the next positional argument is a file
+    let mut fields = false;
+    while let Some(arg) = args.next() {
+        let Some(s) = arg.to_str() else {
+            expanded.push(arg);
+            continue;
+        };
+        let is_positional = !s.starts_with('-')
+            || s == "-"
+            || s[1..].starts_with(|c: char| c.is_ascii_digit() || c == ':');
+        if s == "--" || (is_positional && fields) {
+            expanded.push(arg);
+            expanded.extend(args);
+            break;
+        }
+        if is_positional {
+            fields = true;
+        } else if takes_value(s) {
+            expanded.push(arg);
+            expanded.extend(args.next());
+            continue;
+        }
+        expanded.push(expand(s).map_or(arg, OsString::from));
+    }
+    expanded
+}
+
+/// Rewrite the short option with the attached value as the long one, the value can be
+/// separated with `=`, like in `-d=,`, so `-d==` stands for the `=` delimiter
+fn expand(s: &str) -> Option<String> {
+    let long = match s.get(..2)? {
+        "-i" => "in-place",
+        "-d" => "delimiter",
+        "-o" => "output-separator",
+        _ => return None,
+    };
+    let value = &s[2..];
+    if value.is_empty() {
+        return None;
+    }
+    let value = value.strip_prefix('=').unwrap_or(value);
+    Some(format!("--{}={}", long, value))
+}
+
+/// Report the output error and exit, quietly if the downstream pipe was closed
+fn fail(err: io::Error) -> ! {
+    if err.kind() == io::ErrorKind::BrokenPipe {
+        exit(BROKEN_PIPE);
+    }
+    eprintln!("{}", err);
+    exit(IO_ERROR);
 }
 
 fn main() {
-    let args = Args::parse();
-    let sep = unescape(&args.output_separator).unwrap_or(args.output_separator);
+    let mut args = Args::parse_from(expand_attached(std::env::args_os()));
+    if args.explain {
+        explain(&args);
+        return;
+    }
 
-    let mut reader: Reader;
-    let mut out = io::stdout().lock();
+    if args.unique == Some(unique::Mode::Adjacent) && args.unique_limit.is_some() {
+        // only the previous row is remembered
+        usage_error(
+            ErrorKind::ArgumentConflict,
+            "--unique-limit cannot be used with --unique=adjacent",
+        );
+    }
 
-    if args.file.is_empty() {
-        reader = BufReader::new(Box::new(io::stdin()));
-        process_lines(reader, &mut out, &args.fields, &sep);
-    } else {
+    let (knife, logfmt, sep) = configure(&args);
+    let fill = unescape(&args.fill).unwrap_or_else(|| args.fill.clone());
+    let mut status = Status::default();
+
+    if let Some(ref suffix) = args.in_place {
+        if args.file.is_empty() || args.file.iter().any(|path| path == Path::new("-")) {
+            usage_error(
+                ErrorKind::ArgumentConflict,
+                "editing in place needs files, it does not work with Stdin",
+            );
+        }
         for path in &args.file {
-            reader = match File::open(path) {
-                Ok(file) => BufReader::new(Box::new(file)),
-                Err(msg) => {
-                    eprintln!("{}", msg);
-                    std::process::exit(1);
+            let mut processor = paragraphs(extractor(&knife, logfmt.as_ref(), &sep, &args), &args);
+            match edit_in_place(path, suffix, processor.as_mut()) {
+                Ok(extracted) => status.extracted |= extracted,
+                Err(err) => status.report(&path.to_string_lossy(), err),
+            }
+        }
+        exit(status.exit_code());
+    }
+
+    let mut out: Writer = if args.line_buffered || args.follow {
+        // stdout is line-buffered by itself
+        Box::new(io::stdout().lock())
+    } else {
+        Box::new(BufWriter::new(io::stdout().lock()))
+    };
+
+    let processor: Box<dyn Process> = match args.preview {
+        Some(limit) => {
+            let color = io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+            Box::new(Preview::new(&knife, &sep, limit, args.negative, color))
+        }
+        None if args.stats => Box::new(Stats::new(&knife)),
+        None if args.transpose => Box::new(Transpose::new(&knife, &sep, &fill)),
+        None if args.count => Box::new(Count::new(&knife, &sep, args.top, args.ascending)),
+        None if args.summary => {
+            if knife
+                .patterns()
+                .iter()
+                .any(|x| matches!(x, Pattern::Sub(..)))
+            {
+                // the columns are the fields
+                usage_error(
+                    ErrorKind::ArgumentConflict,
+                    "the sub-fields cannot be summarized, select the whole fields",
+                );
+            }
+            Box::new(Summary::new(&knife, &sep, &args.quantiles))
+        }
+        None if args.join.is_some() => {
+            let path = args.join.as_deref().expect("checked above");
+            if path == Path::new("-") {
+                usage_error(ErrorKind::InvalidValue, "the joined file cannot be Stdin");
+            }
+            let reader = open(path).unwrap_or_else(|err| {
+                eprintln!("{}: {}", path.display(), err);
+                exit(IO_ERROR);
+            });
+            if args.follow && args.join_type == join::Kind::Full {
+                usage_error(
+                    ErrorKind::ArgumentConflict,
+                    "the full join prints the unmatched lines of the joined file at the end, \
+                    it cannot be used with --follow",
+                );
+            }
+            let join_key = args.join_key.as_ref().unwrap_or(&args.key);
+            let join = Join::new(
+                &knife,
+                &sep,
+                args.join_type,
+                args.key.clone(),
+                join_key,
+                &args.join_fields,
+                reader,
+            )
+            .unwrap_or_else(|err| {
+                eprintln!("{}: {}", path.display(), err);
+                exit(IO_ERROR);
+            });
+            Box::new(join)
+        }
+        None => match args.split_by {
+            Some(key) => {
+                if !args.split_path.contains(split::PLACEHOLDER) {
+                    usage_error(
+                        ErrorKind::InvalidValue,
+                        "the --split-path template needs the {} placeholder for the key",
+                    );
                 }
-            };
-            process_lines(reader, &mut out, &args.fields, &sep);
+                Box::new(Split::new(
+                    &knife,
+                    &sep,
+                    key,
+                    &args.split_path,
+                    args.max_open,
+                    args.line_buffered || args.follow,
+                ))
+            }
+            None => extractor(&knife, logfmt.as_ref(), &sep, &args),
+        },
+    };
+    let mut processor = paragraphs(processor, &args);
+
+    if args.follow && !args.file.is_empty() {
+        if args.file.iter().any(|path| path == Path::new("-")) {
+            usage_error(ErrorKind::ArgumentConflict, "Stdin cannot be followed");
         }
+        follow_files(&args.file, &mut out, processor.as_mut()).unwrap_or_else(|err| fail(err));
+    }
+
+    if args.file.is_empty() {
+        // without the files, Stdin is read till the end, also when following
+        args.file.push(PathBuf::from("-"));
+    }
+    for path in &args.file {
+        let name = path.to_string_lossy();
+        let reader = match open(path) {
+            Ok(reader) => reader,
+            Err(err) => {
+                // carry on with the other files
+                status.report(&name, err);
+                continue;
+            }
+        };
+        process_lines(reader, &name, &mut out, processor.as_mut(), &mut status)
+            .unwrap_or_else(|err| fail(err));
+    }
+    processor.finish(&mut out).unwrap_or_else(|err| fail(err));
+
+    out.flush().unwrap_or_else(|err| fail(err));
+    exit(status.exit_code());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsString;
+    use test_case::test_case;
+
+    #[test_case(&["knife", "-i", "1", "a.txt"], &["knife", "-i", "1", "a.txt"]; "without suffix")]
+    #[test_case(&["knife", "-i.bak", "1"], &["knife", "--in-place=.bak", "1"]; "with suffix")]
+    #[test_case(&["knife", "-i=.bak", "1"], &["knife", "--in-place=.bak", "1"]; "with equals sign")]
+    #[test_case(&["knife", "1", "--", "-i.bak"], &["knife", "1", "--", "-i.bak"]; "file name")]
+    #[test_case(&["knife", "-d,", "-o\\t", "1"], &["knife", "--delimiter=,", "--output-separator=\\t", "1"]; "delimiter and separator")]
+    #[test_case(&["knife", "-d", ":", "1"], &["knife", "-d", ":", "1"]; "delimiter not attached")]
+    #[test_case(&["knife", "-d=,", "-o=:", "1"], &["knife", "--delimiter=,", "--output-separator=:", "1"]; "with equals signs")]
+    #[test_case(&["knife", "-d==", "1"], &["knife", "--delimiter==", "1"]; "equals sign delimiter")]
+    #[test_case(&["knife", "-o", "-ish", "1,2"], &["knife", "-o", "-ish", "1,2"]; "value of option")]
+    #[test_case(&["knife", "--fill", "-d", "1", "-o:"], &["knife", "--fill", "-d", "1", "--output-separator=:"]; "option after fields")]
+    #[test_case(&["knife", "-3", "f.txt", "-d.txt"], &["knife", "-3", "f.txt", "-d.txt"]; "file after fields")]
+    #[test_case(&["knife", "-i", "1", "a.txt", "-o.txt"], &["knife", "-i", "1", "a.txt", "-o.txt"]; "in place files")]
+    fn expand_attached(args: &[&str], expected: &[&str]) {
+        let args = args.iter().map(OsString::from);
+        let expected: Vec<OsString> = expected.iter().map(OsString::from).collect();
+        assert_eq!(super::expand_attached(args), expected);
     }
 }
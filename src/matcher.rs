@@ -1,113 +1,229 @@
-use std::{
-    cmp::Ordering,
-    iter::{Enumerate, Skip, Take},
-};
+use crate::parser::Error;
+use std::iter::{Enumerate, Skip, Take};
 
-/// The indexes to be matched
+/// The indexes to be matched, the indexing starts at 0, more kinds of patterns may be added
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
 pub enum Pattern {
+    /// Single index
     Value(usize),
+    /// Closed range of indexes, `usize::MAX` as the upper bound stands for no bound
     Range(usize, usize),
+    /// Single sub-field of the field, the index of the field followed by the index of the sub-field
+    Sub(usize, usize),
 }
 
 impl Pattern {
     /// Smallest index in the pattern
-    fn min(self) -> usize {
-        use Pattern::{Range, Value};
+    pub(crate) fn min(self) -> usize {
+        use Pattern::{Range, Sub, Value};
         match self {
             Value(val) => val,
             Range(val, _) => val,
+            Sub(val, _) => val,
         }
     }
 
     /// Largest index in the pattern
-    fn max(self) -> usize {
-        use Pattern::{Range, Value};
+    pub(crate) fn max(self) -> usize {
+        use Pattern::{Range, Sub, Value};
         match self {
             Value(val) => val,
             Range(_, val) => val,
+            Sub(val, _) => val,
+        }
+    }
+
+    /// Create the pattern from the closed range of indexes
+    fn from_bounds(min: usize, max: usize) -> Self {
+        use Pattern::{Range, Value};
+        if min == max {
+            Value(min)
+        } else {
+            Range(min, max)
+        }
+    }
+}
+
+/// Sort the patterns and merge the overlapping or adjacent ones
+fn normalize(mut pattern: Vec<Pattern>) -> Vec<Pattern> {
+    pattern.sort_unstable_by_key(|x| x.min());
+
+    let mut merged: Vec<Pattern> = Vec::with_capacity(pattern.len());
+    for next in pattern {
+        match merged.last_mut() {
+            Some(last) if next.min() <= last.max().saturating_add(1) => {
+                *last = Pattern::from_bounds(last.min(), last.max().max(next.max()));
+            }
+            _ => merged.push(next),
         }
     }
+    merged
+}
+
+/// Patterns with all the indexes below this limit are matched using a bitset
+const DENSE_LIMIT: usize = 4096;
+
+/// Set of indexes stored as bits
+#[derive(Debug, PartialEq, Clone)]
+struct Bitset(Vec<u64>);
+
+impl Bitset {
+    /// Create the bitset from the patterns, it is assumed that they are bounded
+    fn new(pattern: &[Pattern]) -> Self {
+        let size = pattern.last().map_or(0, |x| x.max() / 64 + 1);
+        let mut bits = vec![0; size];
+        for index in pattern.iter().flat_map(|x| x.min()..=x.max()) {
+            bits[index / 64] |= 1 << (index % 64);
+        }
+        Self(bits)
+    }
+
+    /// Check if the `index` is in the set
+    #[inline]
+    fn contains(&self, index: usize) -> bool {
+        self.0
+            .get(index / 64)
+            .is_some_and(|bits| bits & (1 << (index % 64)) != 0)
+    }
 }
 
 /// Matches the pattern iteratively, in linear time or faster
+///
+/// The patterns are kept as a sorted list of disjoint intervals, for the patterns
+/// with only small indexes, they are additionally stored as a bitset.
 #[derive(Debug, PartialEq, Clone)]
 pub struct Matcher {
-    position: usize,
     pattern: Vec<Pattern>,
+    dense: Option<Bitset>,
     min: usize,
     max: usize,
 }
 
 impl Matcher {
-    /// Create new `Matcher`
-    pub fn new(mut pattern: Vec<Pattern>) -> Self {
-        // the patterns need to be sorted if we want to iterate over them
-        pattern.sort_by_key(|x| x.min());
+    /// Create new `Matcher`, the matcher has no sub-delimiter, so it fails with
+    /// [`Error::NotPositional`] if any of the patterns is [`Pattern::Sub`],
+    /// use [`Knife`](crate::Knife) for selecting the sub-fields
+    pub fn new(pattern: Vec<Pattern>) -> Result<Self, Error> {
+        if pattern.iter().any(|x| matches!(x, Pattern::Sub(..))) {
+            return Err(Error::NotPositional);
+        }
+        Ok(Self::from_positional(pattern))
+    }
+
+    /// Create the `Matcher` from the patterns known not to contain [`Pattern::Sub`]
+    pub(crate) fn from_positional(pattern: Vec<Pattern>) -> Self {
+        // merged patterns can be matched by moving forward over them
+        let pattern = normalize(pattern);
 
         // the bounds are known
-        let min = pattern.iter().map(|x| x.min()).min().unwrap_or(0);
-        let max = pattern.iter().map(|x| x.max()).max().unwrap_or(usize::MAX);
+        let min = pattern.first().map_or(0, |x| x.min());
+        let max = pattern.last().map_or(0, |x| x.max());
+
+        let dense = if max < DENSE_LIMIT {
+            Some(Bitset::new(&pattern))
+        } else {
+            None
+        };
 
         Self {
-            position: 0,
             pattern,
+            dense,
             min,
             max,
         }
     }
 
-    /// Check if pattern contains the `index`
-    #[inline]
-    fn contains(&mut self, index: usize) -> bool {
-        use Pattern::{Range, Value};
+    /// Check if the matcher selects no indexes
+    pub fn is_empty(&self) -> bool {
+        self.pattern.is_empty()
+    }
 
-        if self.position >= self.pattern.len() {
-            // exhausted the patterns
-            return false;
+    /// Check if the `index` is selected by the matcher, unlike when iterating,
+    /// the indexes can be checked in any order
+    pub fn contains(&self, index: usize) -> bool {
+        if let Some(ref dense) = self.dense {
+            return dense.contains(index);
         }
+        // the patterns are sorted and disjoint, so the binary search works
+        let position = self.pattern.partition_point(|x| x.max() < index);
+        self.pattern.get(position).is_some_and(|x| x.min() <= index)
+    }
 
-        let pattern = self.pattern[self.position];
-        match pattern {
-            Value(ref val) => match index.cmp(val) {
-                Ordering::Less => {
-                    // index is not yet there
-                    false
-                }
-                Ordering::Equal => {
-                    // it's a match, move to the next pattern
-                    self.position += 1;
-                    true
-                }
-                Ordering::Greater => {
-                    // check the next pattern
-                    self.position += 1;
-                    self.contains(index)
+    /// Iterate over the selected indexes in increasing order, returns `None` if the pattern is not bounded
+    pub fn indices(&self) -> Option<impl Iterator<Item = usize> + '_> {
+        if self.max == usize::MAX {
+            return None;
+        }
+        Some(self.pattern.iter().flat_map(|x| x.min()..=x.max()))
+    }
+
+    /// The patterns selecting the same indexes as the matcher, sorted, with the overlapping
+    /// and adjacent patterns merged
+    pub fn patterns(&self) -> &[Pattern] {
+        &self.pattern
+    }
+
+    /// Matcher selecting the indexes selected by any of the matchers
+    pub fn union(&self, other: &Matcher) -> Matcher {
+        let pattern = [self.patterns(), other.patterns()].concat();
+        Matcher::from_positional(pattern)
+    }
+
+    /// Matcher selecting the indexes selected by both of the matchers
+    pub fn intersection(&self, other: &Matcher) -> Matcher {
+        let (lhs, rhs) = (self.patterns(), other.patterns());
+        let (mut i, mut j) = (0, 0);
+        let mut pattern = Vec::new();
+
+        while i < lhs.len() && j < rhs.len() {
+            let min = lhs[i].min().max(rhs[j].min());
+            let max = lhs[i].max().min(rhs[j].max());
+            if min <= max {
+                pattern.push(Pattern::from_bounds(min, max));
+            }
+            // the one ending first cannot overlap anything else
+            if lhs[i].max() < rhs[j].max() {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        Matcher::from_positional(pattern)
+    }
+
+    /// Matcher selecting the indexes selected by this matcher, but not by the `other`
+    pub fn difference(&self, other: &Matcher) -> Matcher {
+        let excluded = other.patterns();
+        let mut pattern = Vec::new();
+
+        for x in self.patterns() {
+            let mut min = x.min();
+            let mut exhausted = false;
+
+            for y in excluded
+                .iter()
+                .filter(|y| y.max() >= x.min() && y.min() <= x.max())
+            {
+                if y.min() > min {
+                    pattern.push(Pattern::from_bounds(min, y.min() - 1));
                 }
-            },
-            Range(min, max) => {
-                if index < min {
-                    // index is not yet there
-                    false
-                } else if index < max {
-                    // within the range
-                    true
-                } else if index == max {
-                    // reached the boundary, move to the next pattern
-                    self.position += 1;
-                    true
-                } else {
-                    // check the next pattern
-                    self.position += 1;
-                    self.contains(index)
+                if y.max() >= x.max() {
+                    exhausted = true;
+                    break;
                 }
+                min = min.max(y.max() + 1);
+            }
+            if !exhausted {
+                pattern.push(Pattern::from_bounds(min, x.max()));
             }
         }
+        Matcher::from_positional(pattern)
     }
 
     /// Take iterator and return an iterator returning only the items matching the pattern
     #[inline]
-    pub fn iter<I>(self, iterable: I) -> Filter<I>
+    pub fn iter<I>(&self, iterable: I) -> Filter<'_, I>
     where
         I: Iterator,
     {
@@ -115,36 +231,85 @@ impl Matcher {
     }
 }
 
+/// Position in the patterns of the `Matcher`, moving forward as the indexes increase
+#[derive(Debug, Clone)]
+struct Cursor<'a> {
+    pattern: &'a [Pattern],
+    position: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(matcher: &'a Matcher) -> Self {
+        Self {
+            pattern: &matcher.pattern,
+            position: 0,
+        }
+    }
+
+    /// Check if pattern contains the `index`, moving the cursor forward, so the consecutive calls
+    /// need to use non-decreasing indexes
+    #[inline]
+    fn matches(&mut self, index: usize) -> bool {
+        while let Some(pattern) = self.pattern.get(self.position) {
+            if index < pattern.min() {
+                // index is not yet there
+                return false;
+            }
+            if index <= pattern.max() {
+                // within the pattern
+                return true;
+            }
+            // the patterns are disjoint, so this one would never match again
+            self.position += 1;
+        }
+        // exhausted the patterns
+        false
+    }
+}
+
 /// Iterator returning the items filtered using the `Matcher`
-pub struct Filter<I>
+pub struct Filter<'a, I>
 where
     I: Iterator,
 {
-    matcher: Matcher,
+    dense: Option<&'a Bitset>,
+    cursor: Cursor<'a>,
     iterable: Skip<Take<Enumerate<I>>>,
 }
 
-impl<I: Iterator> Filter<I> {
-    fn new(matcher: Matcher, iterable: I) -> Self {
+impl<'a, I: Iterator> Filter<'a, I> {
+    fn new(matcher: &'a Matcher, iterable: I) -> Self {
+        let end = if matcher.is_empty() {
+            0
+        } else {
+            matcher.max.saturating_add(1)
+        };
         let iterable = iterable
             .enumerate()
             // optimization: skip indexes outside of the range of any pattern
-            .take(matcher.max.saturating_add(1))
+            .take(end)
             .skip(matcher.min);
-        Self { matcher, iterable }
+        Self {
+            dense: matcher.dense.as_ref(),
+            cursor: Cursor::new(matcher),
+            iterable,
+        }
     }
 }
 
-impl<I: Iterator> Iterator for Filter<I> {
+impl<I: Iterator> Iterator for Filter<'_, I> {
     type Item = I::Item;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let (index, value) = self.iterable.next()?;
-        if self.matcher.contains(index) {
-            Some(value)
-        } else {
-            // skip this item, try the next one
-            self.next()
+        loop {
+            let (index, value) = self.iterable.next()?;
+            let matched = match self.dense {
+                Some(dense) => dense.contains(index),
+                None => self.cursor.matches(index),
+            };
+            if matched {
+                return Some(value);
+            }
         }
     }
 }
@@ -152,8 +317,8 @@ impl<I: Iterator> Iterator for Filter<I> {
 #[cfg(test)]
 mod tests {
     use super::{
-        Matcher,
-        Pattern::{self, Range, Value},
+        Cursor, Error, Matcher,
+        Pattern::{self, Range, Sub, Value},
     };
     use test_case::test_case;
 
@@ -176,40 +341,59 @@ mod tests {
     #[test_case(&[Range(1, 3), Range(5, 7)], 4, false; "higher than first range and lower than second")]
     #[test_case(&[Range(1, 3), Value(5), Range(6, 7)], 5, true; "matched by value in mixed patterns")]
     #[test_case(&[Range(1, 3), Value(5), Range(6, 7)], 6, true; "matched by second range in mixed patterns")]
-    fn contains(pattern: &[Pattern], example: usize, expected: bool) {
-        let mut matcher = Matcher::new(pattern.to_vec());
-        assert_eq!(matcher.contains(example), expected);
+    fn matches(pattern: &[Pattern], example: usize, expected: bool) {
+        let matcher = Matcher::new(pattern.to_vec()).unwrap();
+        let mut cursor = Cursor::new(&matcher);
+        assert_eq!(cursor.matches(example), expected);
     }
 
     #[test]
     fn lower_than_any_value() {
-        let mut matcher = Matcher::new(vec![Value(1), Value(2), Value(3)]);
-        assert!(!matcher.contains(0), "not matched");
-        assert_eq!(matcher.position, 0, "index not incremented");
+        let matcher = Matcher::new(vec![Value(1), Value(3), Value(5)]).unwrap();
+        let mut cursor = Cursor::new(&matcher);
+        assert!(!cursor.matches(0), "not matched");
+        assert_eq!(cursor.position, 0, "index not incremented");
     }
 
     #[test]
     fn higher_than_any_value() {
-        let mut matcher = Matcher::new(vec![Value(1), Value(2), Value(3)]);
-        assert!(!matcher.contains(6), "not matched");
-        assert_eq!(matcher.position, 3, "index was incremented");
+        let matcher = Matcher::new(vec![Value(1), Value(3), Value(5)]).unwrap();
+        let mut cursor = Cursor::new(&matcher);
+        assert!(!cursor.matches(6), "not matched");
+        assert_eq!(cursor.position, 3, "index was incremented");
 
-        assert!(!matcher.contains(7), "not matched");
-        assert_eq!(matcher.position, 3, "index was not incremented again");
+        assert!(!cursor.matches(7), "not matched");
+        assert_eq!(cursor.position, 3, "index was not incremented again");
     }
 
     #[test]
     fn patterns_overlap() {
-        let mut matcher = Matcher::new(vec![Value(2), Value(2), Value(2)]);
+        let matcher =
+            Matcher::new(vec![Value(2), Value(2), Range(2, 4), Value(2), Value(6)]).unwrap();
+        assert_eq!(
+            matcher.patterns(),
+            [Range(2, 4), Value(6)],
+            "patterns were merged"
+        );
 
-        assert!(matcher.contains(2), "first value was correctly matched");
-        assert_eq!(matcher.position, 1, "index was incremented");
+        let mut cursor = Cursor::new(&matcher);
+        assert!(cursor.matches(2), "first value was correctly matched");
+        assert_eq!(cursor.position, 0, "index was not incremented");
 
-        assert!(
-            !matcher.contains(3),
-            "second value was correctly not matched"
-        );
-        assert_eq!(matcher.position, 3, "indexes were skipped as expected");
+        assert!(!cursor.matches(5), "gap was correctly not matched");
+        assert_eq!(cursor.position, 1, "index was incremented once");
+    }
+
+    #[test]
+    fn many_patterns_skipped() {
+        // used to recurse once per skipped pattern
+        let pattern: Vec<Pattern> = (0..1_000_000).map(|x| Value(x * 2)).collect();
+        let matcher = Matcher::new(pattern).unwrap();
+        assert!(matcher.dense.is_none(), "uses the intervals");
+
+        let mut cursor = Cursor::new(&matcher);
+        assert!(!cursor.matches(usize::MAX - 1));
+        assert_eq!(cursor.position, 1_000_000);
     }
 
     #[test_case(&[], &[false, false, false, false, false, false, false, false, false, false]; "empty")]
@@ -253,9 +437,15 @@ mod tests {
         "edge case pattern")
     ]
     fn match_whole_pattern(pattern: &[Pattern], expected: &[bool]) {
-        let mut matcher = Matcher::new(pattern.to_vec());
-        let result: Vec<bool> = (0..=9).map(|x| matcher.contains(x)).collect();
+        let matcher = Matcher::new(pattern.to_vec()).unwrap();
+        let mut cursor = Cursor::new(&matcher);
+        let result: Vec<bool> = (0..=9).map(|x| cursor.matches(x)).collect();
         assert_eq!(&result, expected);
+
+        let result: Vec<bool> = (0..=9)
+            .map(|x| matcher.dense.as_ref().unwrap().contains(x))
+            .collect();
+        assert_eq!(&result, expected, "same as the bitset");
     }
 
     #[test_case(&[], &[]; "empty")]
@@ -264,9 +454,66 @@ mod tests {
     #[test_case(&[Range(7, 12)], &[7, 8, 9]; "range exceeds input")]
     #[test_case(&[Range(2, 4), Range(7, 8)], &[2, 3, 4, 7, 8]; "two ranges")]
     fn filter(pattern: &[Pattern], expected: &[u32]) {
-        let matcher = Matcher::new(pattern.to_vec());
+        let matcher = Matcher::new(pattern.to_vec()).unwrap();
         let filter = matcher.iter(0..=9);
         let result: Vec<u32> = filter.collect();
         assert_eq!(result, expected);
     }
+
+    #[test_case(&[Value(5_000), Range(6_000, 6_002)], &[5_000, 6_000, 6_001, 6_002]; "sparse")]
+    #[test_case(&[Range(9_998, usize::MAX)], &[9_998, 9_999]; "unbounded")]
+    fn filter_without_bitset(pattern: &[Pattern], expected: &[u32]) {
+        let matcher = Matcher::new(pattern.to_vec()).unwrap();
+        assert!(matcher.dense.is_none());
+        let result: Vec<u32> = matcher.iter(0..10_000).collect();
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn contains_without_bitset() {
+        let matcher = Matcher::new(vec![Range(10, 20), Value(30), Range(9_000, 9_005)]).unwrap();
+        assert!(matcher.dense.is_none());
+        for index in [9, 21, 29, 31, 8_999, 9_006] {
+            assert!(!matcher.contains(index), "{} not matched", index);
+        }
+        for index in [10, 15, 20, 30, 9_000, 9_005] {
+            assert!(matcher.contains(index), "{} matched", index);
+        }
+    }
+
+    #[test_case(&[], &[]; "empty")]
+    #[test_case(&[Value(3), Value(1), Value(2)], &[Range(1, 3)]; "adjacent values")]
+    #[test_case(&[Range(5, 7), Range(1, 3)], &[Range(1, 3), Range(5, 7)]; "sorted")]
+    #[test_case(&[Range(1, 5), Value(3), Range(4, 9)], &[Range(1, 9)]; "overlapping")]
+    #[test_case(&[Range(3, usize::MAX), Range(0, 2)], &[Range(0, usize::MAX)]; "unbounded")]
+    #[test_case(&[Value(2), Value(2)], &[Value(2)]; "duplicates")]
+    fn normalize(pattern: &[Pattern], expected: &[Pattern]) {
+        assert_eq!(super::normalize(pattern.to_vec()), expected);
+    }
+
+    #[test]
+    fn sub_field() {
+        assert_eq!(
+            Matcher::new(vec![Sub(2, 1), Value(3)]),
+            Err(Error::NotPositional)
+        );
+    }
+
+    #[test_case(&[Range(1, 3), Value(5)], &[false, true, true, true, false, true, false]; "mixed")]
+    #[test_case(&[Value(5), Range(1, 3)], &[false, true, true, true, false, true, false]; "any order")]
+    fn contains(pattern: &[Pattern], expected: &[bool]) {
+        let matcher = Matcher::new(pattern.to_vec()).unwrap();
+        // check in reversed order, it should not matter
+        let result: Vec<bool> = (0..=6).rev().map(|x| matcher.contains(x)).collect();
+        let expected: Vec<bool> = expected.iter().rev().copied().collect();
+        assert_eq!(result, expected);
+    }
+
+    #[test_case(&[Range(1, 3), Value(2), Value(7)], Some(&[1, 2, 3, 7]); "bounded")]
+    #[test_case(&[Value(1), Range(5, usize::MAX)], None; "unbounded")]
+    fn indices(pattern: &[Pattern], expected: Option<&[usize]>) {
+        let matcher = Matcher::new(pattern.to_vec()).unwrap();
+        let result: Option<Vec<usize>> = matcher.indices().map(|x| x.collect());
+        assert_eq!(result.as_deref(), expected);
+    }
 }
@@ -0,0 +1,90 @@
+use crate::process::Process;
+use std::io::{self, Write};
+
+/// Gathers the lines into the records ending at the separator lines, by default at the blank
+/// lines, and passes the records to the `inner` processor as if they were single lines,
+/// the lines of a record are joined with newlines, so they are split into the fields
+/// by the whitespaces
+pub struct Paragraphs<'a> {
+    inner: Box<dyn Process + 'a>,
+    separator: String,
+    record: String,
+}
+
+impl<'a> Paragraphs<'a> {
+    /// The records end at the lines equal to the `separator`, ignoring the surrounding
+    /// whitespaces, an empty `separator` stands for the blank lines
+    pub fn new(inner: Box<dyn Process + 'a>, separator: &str) -> Self {
+        Self {
+            inner,
+            separator: separator.trim().to_string(),
+            record: String::new(),
+        }
+    }
+
+    /// Pass the gathered record to the inner processor, the empty records are skipped
+    fn flush(&mut self, out: &mut dyn Write) -> io::Result<bool> {
+        if self.record.is_empty() {
+            return Ok(false);
+        }
+        let extracted = self.inner.line(&self.record, out)?;
+        self.record.clear();
+        Ok(extracted)
+    }
+}
+
+impl Process for Paragraphs<'_> {
+    fn line(&mut self, line: &str, out: &mut dyn Write) -> io::Result<bool> {
+        if line.trim() == self.separator {
+            return self.flush(out);
+        }
+        if !self.record.is_empty() {
+            self.record.push('\n');
+        }
+        self.record.push_str(line);
+        Ok(false)
+    }
+
+    /// The records do not span the inputs
+    fn end_input(&mut self, name: &str, out: &mut dyn Write) -> io::Result<bool> {
+        let extracted = self.flush(out)?;
+        Ok(self.inner.end_input(name, out)? || extracted)
+    }
+
+    fn finish(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        self.inner.finish(out)
+    }
+
+    fn is_done(&self) -> bool {
+        self.inner.is_done()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Paragraphs;
+    use crate::process::{Cutter, Extract, Process};
+    use knife::Knife;
+    use std::str::FromStr;
+    use test_case::test_case;
+
+    #[test_case("", "a 1\nb 2\n\nc 3\n", "a 2\nc\n"; "blank lines")]
+    #[test_case("", "\n\na 1\n  \n\nb 2\n", "a\nb\n"; "repeated blank lines")]
+    #[test_case("%", "a 1\n\nb 2\n%\nc\n%\n", "a 2\nc\n"; "custom separator")]
+    #[test_case("", "", ""; "empty")]
+    fn records(separator: &str, text: &str, expected: &str) {
+        let knife: Cutter = Knife::from_str("1,4")
+            .unwrap()
+            .with_tokenizer(Box::new(knife::tokenizer::Whitespace));
+        let mut paragraphs = Paragraphs::new(Box::new(Extract::new(&knife, " ")), separator);
+        let mut out = Vec::new();
+        let mut extracted = false;
+        for line in text.lines() {
+            extracted |= paragraphs.line(line, &mut out).unwrap();
+        }
+        extracted |= paragraphs.end_input("-", &mut out).unwrap();
+        paragraphs.finish(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+        assert_eq!(extracted, !expected.is_empty());
+    }
+}
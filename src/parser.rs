@@ -1,14 +1,30 @@
-use crate::matcher::Pattern::{self, Range, Value};
-use std::{cmp::Ordering, fmt::Display};
+use crate::{
+    content::Content,
+    matcher::{
+        Matcher,
+        Pattern::{self, Range, Sub, Value},
+    },
+};
+use std::{cmp::Ordering, fmt::Display, str::FromStr};
 
 const MIN: usize = 1;
 const MAX: usize = usize::MAX;
 
+/// Errors raised when parsing the patterns, more kinds of errors may be added
 #[derive(Debug, PartialEq)]
+#[non_exhaustive]
 pub enum Error {
+    /// The pattern contains invalid characters
     CannotParse,
+    /// The fields are numbered starting at 1, so 0 is not a valid field
     StartsAtOne,
+    /// The pattern does not specify any fields
     Empty,
+    /// The regular expression used for selecting the fields by their content is not valid
+    InvalidRegex(String),
+    /// Only the field numbers and ranges can be used, like in [`Matcher`], not the sub-fields
+    /// or the content patterns
+    NotPositional,
 }
 
 impl std::error::Error for Error {}
@@ -20,6 +36,8 @@ impl Display for Error {
             CannotParse => "cannot parse the pattern",
             Empty => "no fields specified",
             StartsAtOne => "numbering starts at 1",
+            InvalidRegex(err) => return write!(f, "invalid regular expression: {}", err),
+            NotPositional => "only the field numbers and ranges are supported",
         };
         write!(f, "{}", msg)
     }
@@ -55,6 +73,61 @@ impl Pattern {
             Ordering::Equal => Self::maybe_value(min),
         }
     }
+
+    /// Validate the values and transform from 1-based indexing to 0-based, return `Pattern::Sub`
+    fn maybe_sub(field: usize, sub: usize) -> Result<Self, Error> {
+        if field < MIN || sub < MIN {
+            return Err(Error::StartsAtOne);
+        }
+        Ok(Sub(change_base(field), change_base(sub)))
+    }
+}
+
+/// Display using the 1-based pattern language, the inverse of parsing
+impl Display for Pattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Value(val) => write!(f, "{}", val + 1),
+            Range(min, MAX) => write!(f, "{}-", min + 1),
+            Range(min, max) => write!(f, "{}-{}", min + 1, max + 1),
+            Sub(field, sub) => write!(f, "{}.{}", field + 1, sub + 1),
+        }
+    }
+}
+
+/// Display the normalized patterns using the 1-based pattern language
+///
+/// The empty matcher, like the one left by `difference`, is displayed as the empty string,
+/// which does not parse, since selecting no fields is an [`Error::Empty`].
+impl Display for Matcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, pattern) in self.patterns().iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}", pattern)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse the pattern, the sub-fields and the content patterns are rejected, since the matcher
+/// selects the fields only by their positions
+impl FromStr for Matcher {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (patterns, content) = with_content(s)?;
+        matcher(patterns, &content)
+    }
+}
+
+/// Create the matcher, checking that only the field numbers and ranges are used
+pub(crate) fn matcher(patterns: Vec<Pattern>, content: &[Content]) -> Result<Matcher, Error> {
+    if !content.is_empty() {
+        return Err(Error::NotPositional);
+    }
+    Matcher::new(patterns)
 }
 
 /// Try parsing characters as an integer
@@ -70,20 +143,39 @@ fn try_parse_usize(chars: &[char]) -> Option<usize> {
     Some(chars.iter().fold(0, |acc, c| acc * 10 + *c as usize - 48))
 }
 
-/// Parse patterns from a string
+/// Parse patterns from a string using the 1-based pattern language, where N stands for the N-th field,
+/// -N, N-, and N-M for the ranges (`:` can be used instead of `-`), N.M for the M-th sub-field
+/// of the N-th field, and commas for combining them
+///
+/// The returned patterns use 0-based indexes.
 pub fn from_str(s: &str) -> Result<Vec<Pattern>, Error> {
+    parse(s, &mut Vec::new())
+}
+
+/// Parse patterns from a string, like `from_str`, and collect the ranges that were given
+/// in the reversed order, like `5-2`, to `reversed`, after they were swapped
+pub(crate) fn parse(s: &str, reversed: &mut Vec<Pattern>) -> Result<Vec<Pattern>, Error> {
     /// On reaching the boundary of the field collect it
     #[inline]
     fn collect(
         patterns: &mut Vec<Pattern>,
+        reversed: &mut Vec<Pattern>,
         digits: &[char],
         range_start: usize,
         is_range: bool,
+        sub_of: Option<usize>,
     ) -> Result<(), Error> {
         let num = try_parse_usize(digits);
-        if is_range {
+        if let Some(field) = sub_of {
+            let sub = num.ok_or(Error::CannotParse)?;
+            patterns.push(Pattern::maybe_sub(field, sub)?);
+        } else if is_range {
             let range_end = num.unwrap_or(MAX);
-            patterns.push(Pattern::maybe_range(range_start, range_end)?);
+            let pattern = Pattern::maybe_range(range_start, range_end)?;
+            if range_start > range_end {
+                reversed.push(pattern);
+            }
+            patterns.push(pattern);
         } else if let Some(num) = num {
             patterns.push(Pattern::maybe_value(num)?)
         }
@@ -95,6 +187,7 @@ pub fn from_str(s: &str) -> Result<Vec<Pattern>, Error> {
     let mut range_start = MIN;
     let mut digits = Vec::new();
     let mut is_range = false;
+    let mut sub_of = None;
 
     // the parser
     for c in s.chars() {
@@ -103,18 +196,35 @@ pub fn from_str(s: &str) -> Result<Vec<Pattern>, Error> {
                 // collect the digits
                 digits.push(c)
             }
+            '-' | ':' if sub_of.is_some() => return Err(Error::CannotParse),
             '-' | ':' => {
                 // it is a range, try parsing the lower bound and start parsing the upper bound
                 range_start = try_parse_usize(&digits).unwrap_or(MIN);
                 digits.clear();
                 is_range = true;
             }
+            '.' => {
+                // the field number is followed by the sub-field number
+                if is_range || sub_of.is_some() {
+                    return Err(Error::CannotParse);
+                }
+                sub_of = Some(try_parse_usize(&digits).ok_or(Error::CannotParse)?);
+                digits.clear();
+            }
             ',' => {
                 // collect previous value and start parsing new one
-                collect(&mut patterns, &digits, range_start, is_range)?;
+                collect(
+                    &mut patterns,
+                    reversed,
+                    &digits,
+                    range_start,
+                    is_range,
+                    sub_of,
+                )?;
                 digits.clear();
                 range_start = MIN;
                 is_range = false;
+                sub_of = None;
             }
             c => {
                 if !c.is_whitespace() {
@@ -125,7 +235,14 @@ pub fn from_str(s: &str) -> Result<Vec<Pattern>, Error> {
     }
 
     // the last pattern is not delimited by `,` so we need to collect it here
-    collect(&mut patterns, &digits, range_start, is_range)?;
+    collect(
+        &mut patterns,
+        reversed,
+        &digits,
+        range_start,
+        is_range,
+        sub_of,
+    )?;
 
     if patterns.is_empty() {
         Err(Error::Empty)
@@ -134,10 +251,96 @@ pub fn from_str(s: &str) -> Result<Vec<Pattern>, Error> {
     }
 }
 
+/// Parse the patterns selecting the fields by their positions, like `from_str`, mixed with
+/// the ones selecting them by their content, like `/regex/` for the fields matching the regular
+/// expression, or `/regex/+N` for the N-th field after them (the `N` is 1 by default)
+///
+/// The `/` inside of the regular expression needs to be escaped as `\/`.
+pub fn with_content(s: &str) -> Result<(Vec<Pattern>, Vec<Content>), Error> {
+    parse_with_content(s, &mut Vec::new())
+}
+
+/// Parse the patterns, like `with_content`, and collect the ranges that were given
+/// in the reversed order to `reversed`
+pub(crate) fn parse_with_content(
+    s: &str,
+    reversed: &mut Vec<Pattern>,
+) -> Result<(Vec<Pattern>, Vec<Content>), Error> {
+    let (positional, content) = split_content(s)?;
+    if content.is_empty() {
+        return Ok((parse(s, reversed)?, content));
+    }
+    match parse(&positional, reversed) {
+        Ok(patterns) => Ok((patterns, content)),
+        // the content patterns are enough
+        Err(Error::Empty) => Ok((Vec::new(), content)),
+        Err(err) => Err(err),
+    }
+}
+
+/// Take out the content patterns from the string, return the rest of the string
+/// and the parsed content patterns
+fn split_content(s: &str) -> Result<(String, Vec<Content>), Error> {
+    let mut positional = String::new();
+    let mut content = Vec::new();
+    let mut chars = s.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '/' {
+            positional.push(c);
+            continue;
+        }
+        // the content pattern cannot be glued to the positional one
+        if positional.ends_with(|c: char| c != ',' && !c.is_whitespace()) {
+            return Err(Error::CannotParse);
+        }
+
+        let mut regex = String::new();
+        let mut closed = false;
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' if chars.peek() == Some(&'/') => {
+                    regex.push('/');
+                    chars.next();
+                }
+                '/' => {
+                    closed = true;
+                    break;
+                }
+                c => regex.push(c),
+            }
+        }
+        if !closed {
+            return Err(Error::CannotParse);
+        }
+
+        let mut offset = 0;
+        if chars.next_if_eq(&'+').is_some() {
+            let mut digits = Vec::new();
+            while let Some(c) = chars.next_if(char::is_ascii_digit) {
+                digits.push(c);
+            }
+            offset = try_parse_usize(&digits).unwrap_or(1);
+        }
+        if chars
+            .peek()
+            .is_some_and(|&c| c != ',' && !c.is_whitespace())
+        {
+            return Err(Error::CannotParse);
+        }
+
+        content.push(Content::new(&regex, offset)?);
+    }
+    Ok((positional, content))
+}
+
 #[cfg(test)]
 mod tests {
     use super::Error;
-    use crate::matcher::Pattern::{self, Range, Value};
+    use crate::matcher::{
+        Matcher,
+        Pattern::{self, Range, Sub, Value},
+    };
     use test_case::test_case;
 
     #[test_case(&[], None; "empty")]
@@ -163,6 +366,8 @@ mod tests {
     #[test_case("1-2, 4-5", &[Range(0, 1), Range(3, 4)]; "two ranges")]
     #[test_case("-3, 4, 5-7, 9-", &[Range(0, 2), Value(3), Range(4, 6), Range(8, usize::MAX)]; "mixed")]
     #[test_case("1:3,:5,5:", &[Range(0, 2), Range(0, 4), Range(4, usize::MAX)]; "ranges defined using colons")]
+    #[test_case("3.2", &[Sub(2, 1)]; "sub-field")]
+    #[test_case("1, 3.1, 3.12", &[Value(0), Sub(2, 0), Sub(2, 11)]; "sub-fields")]
     fn from_str(input: &str, expected: &[Pattern]) {
         assert_eq!(super::from_str(input).unwrap(), expected);
     }
@@ -173,10 +378,24 @@ mod tests {
     #[test_case("1-%^&5"; "invalid chars")]
     #[test_case("a-z"; "non-numbers")]
     #[test_case("1-5, 3, X, 7-9"; "invalid char in the middle")]
+    #[test_case("3."; "sub-field missing")]
+    #[test_case(".2"; "field missing")]
+    #[test_case("3.0"; "sub-field starts at 1")]
+    #[test_case("3.2.1"; "nested sub-fields")]
+    #[test_case("1-3.2"; "sub-field of range")]
+    #[test_case("3.1-2"; "range of sub-fields")]
     fn from_str_raises_error(example: &str) {
         assert!(super::from_str(example).is_err());
     }
 
+    #[test_case("1-3, 5", &[]; "not reversed")]
+    #[test_case("5-2, 7:3, 4-4, 9-", &[Range(1, 4), Range(2, 6)]; "reversed")]
+    fn parse_reversed(input: &str, expected: &[Pattern]) {
+        let mut reversed = Vec::new();
+        super::parse(input, &mut reversed).unwrap();
+        assert_eq!(reversed, expected);
+    }
+
     #[test_case(0, Err(Error::StartsAtOne); "zero")]
     #[test_case(42, Ok(Value(41)); "value")]
     fn maybe_value(example: usize, expected: Result<Pattern, Error>) {
@@ -190,4 +409,64 @@ mod tests {
     fn maybe_range(min: usize, max: usize, expected: Result<Pattern, Error>) {
         assert_eq!(Pattern::maybe_range(min, max), expected)
     }
+
+    #[test_case("7", "7"; "single value")]
+    #[test_case("-3", "1-3"; "range without start")]
+    #[test_case("3-", "3-"; "range without end")]
+    #[test_case("5-2", "2-5"; "reversed range")]
+    #[test_case("9-, 1,2, 3-5, 4", "1-5,9-"; "normalized")]
+    fn display(input: &str, expected: &str) {
+        let matcher = Matcher::new(super::from_str(input).unwrap()).unwrap();
+        assert_eq!(matcher.to_string(), expected);
+    }
+
+    #[test_case("1, 3-5", "1,3-5"; "positional")]
+    #[test_case("3.2", "!"; "sub-field")]
+    #[test_case("1, /x/", "!"; "content")]
+    fn matcher_from_str(input: &str, expected: &str) {
+        match input.parse::<Matcher>() {
+            Ok(matcher) => assert_eq!(matcher.to_string(), expected),
+            Err(err) => assert_eq!((err, expected), (Error::NotPositional, "!")),
+        }
+    }
+
+    #[test]
+    fn display_empty() {
+        let matcher = Matcher::new(Vec::new()).unwrap();
+        assert_eq!(matcher.to_string(), "");
+        assert_eq!(super::from_str(&matcher.to_string()), Err(Error::Empty));
+    }
+
+    #[test_case("/^user:/", &[], &["/^user:/"]; "content")]
+    #[test_case("1, /^-p$/+, 3-", &[Value(0), Range(2, usize::MAX)], &["/^-p$/+"]; "mixed")]
+    #[test_case("/a,b/+2, /c\\/d/", &[], &["/a,b/+2", "/c\\/d/"]; "commas and slashes")]
+    #[test_case("2", &[Value(1)], &[]; "positional")]
+    fn with_content(input: &str, patterns: &[Pattern], content: &[&str]) {
+        let (parsed, parsed_content) = super::with_content(input).unwrap();
+        assert_eq!(parsed, patterns);
+        let parsed_content: Vec<String> = parsed_content.iter().map(|x| x.to_string()).collect();
+        assert_eq!(parsed_content, content);
+    }
+
+    #[test_case("/a", Error::CannotParse; "unclosed")]
+    #[test_case("1/a/", Error::CannotParse; "glued before")]
+    #[test_case("/a/1", Error::CannotParse; "glued after")]
+    #[test_case("/a/, 0", Error::StartsAtOne; "invalid positional")]
+    #[test_case(",", Error::Empty; "empty")]
+    fn with_content_raises_error(input: &str, expected: Error) {
+        assert_eq!(super::with_content(input).unwrap_err(), expected);
+    }
+
+    #[test]
+    fn invalid_regex() {
+        assert!(matches!(
+            super::with_content("/(/"),
+            Err(Error::InvalidRegex(_))
+        ));
+    }
+
+    #[test]
+    fn display_sub() {
+        assert_eq!(Sub(2, 11).to_string(), "3.12");
+    }
 }
@@ -0,0 +1,114 @@
+use crate::process::{Cutter, Process};
+use knife::Tokenizer;
+use std::io::{self, Write};
+
+/// Prints the lines with the fields annotated with their numbers, to help with picking the fields
+pub struct Preview<'a> {
+    knife: &'a Cutter,
+    sep: &'a str,
+    limit: usize,
+    seen: usize,
+    negative: bool,
+    color: bool,
+}
+
+impl<'a> Preview<'a> {
+    /// Preview the first `limit` lines, if `negative`, also show the field numbers counted from the end,
+    /// if `color`, highlight the numbers and the fields selected by the `knife`
+    pub fn new(knife: &'a Cutter, sep: &'a str, limit: usize, negative: bool, color: bool) -> Self {
+        Self {
+            knife,
+            sep,
+            limit,
+            seen: 0,
+            negative,
+            color,
+        }
+    }
+}
+
+impl Process for Preview<'_> {
+    fn line(&mut self, line: &str, out: &mut dyn Write) -> io::Result<bool> {
+        self.seen += 1;
+        let (annotated, selected) = annotate(self.knife, line, self.negative, self.color);
+        out.write_all(annotated.join(self.sep).as_bytes())?;
+        out.write_all(b"\n")?;
+        Ok(selected)
+    }
+
+    fn is_done(&self) -> bool {
+        self.seen >= self.limit
+    }
+}
+
+/// Annotate the fields with their 1-based numbers, like `[1]Mary`, or with the negative numbers,
+/// like `[1|-5]Mary`, and check if any of them was selected by the `knife`
+///
+/// The fields are found by the same tokenizer that is used for extracting them, and they are
+/// highlighted when they are extracted, so also when selected by their content.
+fn annotate(knife: &Cutter, line: &str, negative: bool, color: bool) -> (Vec<String>, bool) {
+    let fields: Vec<&str> = knife.tokenizer().tokens(line).collect();
+    let count = fields.len();
+    let extracted: Vec<usize> = knife
+        .extract_spans(line)
+        .into_iter()
+        .map(|(number, _, _)| number)
+        .collect();
+    let mut selected = false;
+
+    let annotated = fields
+        .into_iter()
+        .enumerate()
+        .map(|(index, field)| {
+            let number = index + 1;
+            let label = if negative {
+                format!("[{}|-{}]", number, count - index)
+            } else {
+                format!("[{}]", number)
+            };
+            let is_selected = extracted.binary_search(&number).is_ok();
+            selected |= is_selected;
+            match (color, is_selected) {
+                (false, _) => format!("{}{}", label, field),
+                (true, false) => color_print::cformat!("<dim>{}</>{}", label, field),
+                (true, true) => color_print::cformat!("<cyan>{}</><bold>{}</>", label, field),
+            }
+        })
+        .collect();
+    (annotated, selected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::annotate;
+    use crate::process::Cutter;
+    use knife::{tokenizer::Delimiter, Knife, Tokenizer};
+    use std::str::FromStr;
+    use test_case::test_case;
+
+    fn cutter(spec: &str, tokenizer: Box<dyn Tokenizer>) -> Cutter {
+        Knife::from_str(spec).unwrap().with_tokenizer(tokenizer)
+    }
+
+    #[test_case("2", "a b  c", false, &["[1]a", "[2]b", "[3]c"], true; "numbers")]
+    #[test_case("5", "a b c", true, &["[1|-3]a", "[2|-2]b", "[3|-1]c"], false; "negative numbers")]
+    #[test_case("1", "", false, &[], false; "empty line")]
+    #[test_case("/^-p$/+", "ssh -p 22", false, &["[1]ssh", "[2]-p", "[3]22"], true; "content")]
+    #[test_case("/^-p$/+", "ssh host", false, &["[1]ssh", "[2]host"], false; "content not matched")]
+    fn whitespace(spec: &str, line: &str, negative: bool, expected: &[&str], selected: bool) {
+        let knife = cutter(spec, Box::new(knife::tokenizer::Whitespace));
+        assert_eq!(
+            annotate(&knife, line, negative, false),
+            (expected.iter().map(|x| x.to_string()).collect(), selected)
+        );
+    }
+
+    #[test]
+    fn same_tokenizer() {
+        let knife = cutter("3", Box::new(Delimiter(',')));
+        let (annotated, selected) = annotate(&knife, "a b,,c", false, false);
+        assert_eq!(annotated, ["[1]a b", "[2]", "[3]c"]);
+        assert!(selected);
+        assert_eq!(knife.extract("a b,,c"), ["c"], "the numbers match");
+    }
+}
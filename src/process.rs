@@ -0,0 +1,79 @@
+use knife::{logfmt::Logfmt, Knife, Tokenizer};
+use std::io::{self, Write};
+
+/// Knife using the tokenizer picked in the command line
+pub type Cutter = Knife<Box<dyn Tokenizer>>;
+
+/// Processes the lines of the inputs and writes the results
+pub trait Process {
+    /// Process the line, return `true` if any fields were extracted from it
+    fn line(&mut self, line: &str, out: &mut dyn Write) -> io::Result<bool>;
+
+    /// Called instead of `line` for the lines that could not be read, like the invalid UTF-8
+    fn skip_line(&mut self) {}
+
+    /// Called after each of the inputs was read, return `true` if any fields were extracted
+    fn end_input(&mut self, _name: &str, _out: &mut dyn Write) -> io::Result<bool> {
+        Ok(false)
+    }
+
+    /// Called after all the inputs were read
+    fn finish(&mut self, _out: &mut dyn Write) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Check if no more lines are needed
+    fn is_done(&self) -> bool {
+        false
+    }
+}
+
+/// Prints the extracted fields
+pub struct Extract<'a> {
+    knife: &'a Cutter,
+    sep: &'a str,
+}
+
+impl<'a> Extract<'a> {
+    pub fn new(knife: &'a Cutter, sep: &'a str) -> Self {
+        Self { knife, sep }
+    }
+}
+
+impl Process for Extract<'_> {
+    /// Write the fields extracted from the line as a single line
+    #[inline]
+    fn line(&mut self, line: &str, out: &mut dyn Write) -> io::Result<bool> {
+        let fields = self.knife.extract(line);
+        if fields.is_empty() {
+            return Ok(false);
+        }
+        out.write_all(fields.join(self.sep).as_bytes())?;
+        out.write_all(b"\n")?;
+        Ok(true)
+    }
+}
+
+/// Prints the values selected by their keys and the fields from the `key=value` lines
+pub struct ExtractPairs<'a> {
+    logfmt: &'a Logfmt,
+    sep: &'a str,
+}
+
+impl<'a> ExtractPairs<'a> {
+    pub fn new(logfmt: &'a Logfmt, sep: &'a str) -> Self {
+        Self { logfmt, sep }
+    }
+}
+
+impl Process for ExtractPairs<'_> {
+    fn line(&mut self, line: &str, out: &mut dyn Write) -> io::Result<bool> {
+        let values = self.logfmt.extract(line);
+        if values.is_empty() {
+            return Ok(false);
+        }
+        out.write_all(values.join(self.sep).as_bytes())?;
+        out.write_all(b"\n")?;
+        Ok(true)
+    }
+}
@@ -0,0 +1,280 @@
+//! Serialization of the patterns using [serde], available with the `serde` feature.
+//!
+//! [`Knife`], [`Matcher`], and [`Pattern`] are serialized as the canonical 1-based pattern
+//! strings, like `"1,3-5,9-"`. When deserializing, they accept the pattern strings, the field
+//! numbers, or the lists of them, like `[1, "3-5", "9-"]`. Everything is validated by
+//! [`parser::with_content`], so the invalid patterns are rejected with the same errors as in the
+//! command line tool. Only `Knife` accepts the content patterns, like `"/^user:/"`.
+//!
+//! The deserialized `Knife` uses the default tokenizer of its type and the default
+//! [`SUB_DELIMITER`](crate::SUB_DELIMITER), since neither of them is serialized, so after
+//! deserializing a knife that used a custom sub-delimiter, set it again with
+//! [`Knife::with_sub_delimiter`], otherwise `3.2` would select a different sub-field.
+//!
+//! The empty `Knife` or `Matcher`, like the one left by `difference`, is serialized as `""`,
+//! that is rejected when deserializing, like the empty pattern in the command line tool,
+//! so check [`Knife::is_empty`] before serializing them if that can happen.
+//!
+//! The patterns are deserialized with `deserialize_any`, since they can be strings, numbers,
+//! or lists, so only the self-describing formats, like JSON, YAML, or TOML, are supported,
+//! but not the formats like bincode.
+
+use crate::{
+    content::Content,
+    matcher::{Matcher, Pattern},
+    parser::{self, Error},
+    tokenizer::Tokenizer,
+    Knife,
+};
+use serde::{
+    de::{self, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::fmt;
+
+impl Serialize for Pattern {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl Serialize for Matcher {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<T> Serialize for Knife<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Pattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match deserializer.deserialize_any(PatternsVisitor)? {
+            (patterns, content) if patterns.len() == 1 && content.is_empty() => Ok(patterns[0]),
+            _ => Err(de::Error::custom("expected a single field or a range")),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Matcher {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (patterns, content) = deserializer.deserialize_any(PatternsVisitor)?;
+        parser::matcher(patterns, &content).map_err(de::Error::custom)
+    }
+}
+
+impl<'de, T: Tokenizer + Default> Deserialize<'de> for Knife<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (patterns, content) = deserializer.deserialize_any(PatternsVisitor)?;
+        Ok(Knife::new(patterns)
+            .with_content(content)
+            .with_tokenizer(T::default()))
+    }
+}
+
+/// Parses the patterns from the pattern strings, field numbers, or the lists of them
+struct PatternsVisitor;
+
+impl<'de> Visitor<'de> for PatternsVisitor {
+    type Value = (Vec<Pattern>, Vec<Content>);
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "a field number, a pattern like \"1,3-5\", or a list of them"
+        )
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        parser::with_content(v).map_err(E::custom)
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        self.visit_str(&v.to_string())
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        if v < 1 {
+            // otherwise -N would be parsed as a range
+            return Err(E::custom(Error::StartsAtOne));
+        }
+        self.visit_str(&v.to_string())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut patterns = Vec::new();
+        let mut content = Vec::new();
+        while let Some(Patterns((next, more))) = seq.next_element()? {
+            patterns.extend(next);
+            content.extend(more);
+        }
+        if patterns.is_empty() && content.is_empty() {
+            return Err(de::Error::custom(Error::Empty));
+        }
+        Ok((patterns, content))
+    }
+}
+
+/// Elements of the list of patterns
+struct Patterns((Vec<Pattern>, Vec<Content>));
+
+impl<'de> Deserialize<'de> for Patterns {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(PatternsVisitor).map(Patterns)
+    }
+}
+
+/// Serialize [`Knife`] as the structured list of fields and ranges, like `[1, "3-5", "9-", "10.2"]`,
+/// where the single fields are integers and the ranges and sub-fields are strings, use it with
+/// `#[serde(with = "knife::serialize::structured")]`
+pub mod structured {
+    use super::{Knife, Pattern, PatternsVisitor};
+    use crate::tokenizer::Tokenizer;
+    use serde::{ser::SerializeSeq, Deserializer, Serializer};
+
+    pub fn serialize<T, S: Serializer>(knife: &Knife<T>, serializer: S) -> Result<S::Ok, S::Error> {
+        let patterns = knife.patterns();
+        let content = knife.content();
+        let mut seq = serializer.serialize_seq(Some(patterns.len() + content.len()))?;
+        for pattern in patterns {
+            match pattern {
+                Pattern::Value(val) => seq.serialize_element(&(val + 1))?,
+                other => seq.serialize_element(&other)?,
+            }
+        }
+        for content in content {
+            seq.serialize_element(&content.to_string())?;
+        }
+        seq.end()
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Knife<T>, D::Error>
+    where
+        T: Tokenizer + Default,
+        D: Deserializer<'de>,
+    {
+        let (patterns, content) = deserializer.deserialize_any(PatternsVisitor)?;
+        Ok(Knife::new(patterns)
+            .with_content(content)
+            .with_tokenizer(T::default()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        matcher::Pattern::{self, Range, Value},
+        Knife, Matcher,
+    };
+    use serde::{Deserialize, Serialize};
+    use std::str::FromStr;
+    use test_case::test_case;
+
+    #[test_case(Value(0), r#""1""#; "value")]
+    #[test_case(Range(2, 4), r#""3-5""#; "range")]
+    #[test_case(Range(8, usize::MAX), r#""9-""#; "unbounded range")]
+    fn pattern(pattern: Pattern, expected: &str) {
+        let json = serde_json::to_string(&pattern).unwrap();
+        assert_eq!(json, expected);
+        assert_eq!(serde_json::from_str::<Pattern>(&json).unwrap(), pattern);
+    }
+
+    #[test_case("1", Value(0); "integer")]
+    #[test_case(r#""-3""#, Range(0, 2); "string")]
+    #[test_case(r#"["2-4"]"#, Range(1, 3); "list")]
+    fn pattern_from(json: &str, expected: Pattern) {
+        assert_eq!(serde_json::from_str::<Pattern>(json).unwrap(), expected);
+    }
+
+    #[test_case(r#""1,3""#; "multiple patterns")]
+    #[test_case(r#""/x/""#; "content")]
+    #[test_case(r#""x""#; "invalid")]
+    fn pattern_from_invalid(json: &str) {
+        assert!(serde_json::from_str::<Pattern>(json).is_err());
+    }
+
+    #[test_case("-3, 9-, 5, 4", r#""1-5,9-""#; "normalized")]
+    #[test_case("7", r#""7""#; "single field")]
+    #[test_case("3.2, 1", r#""1,3.2""#; "sub-field")]
+    #[test_case("/^-p$/+, 1", r#""1,/^-p$/+""#; "content")]
+    fn knife(spec: &str, expected: &str) {
+        let knife = Knife::from_str(spec).unwrap();
+        let json = serde_json::to_string(&knife).unwrap();
+        assert_eq!(json, expected);
+        assert_eq!(serde_json::from_str::<Knife>(&json).unwrap(), knife);
+    }
+
+    #[test_case(r#""1,3-5""#, "1,3-5"; "string")]
+    #[test_case("2", "2"; "integer")]
+    #[test_case(r#"[1, "3-5", "7,9-"]"#, "1,3-5,7,9-"; "list")]
+    fn knife_from(json: &str, expected: &str) {
+        let knife: Knife = serde_json::from_str(json).unwrap();
+        assert_eq!(knife, Knife::from_str(expected).unwrap());
+    }
+
+    #[test_case(r#""0-5""#, "numbering starts at 1"; "starts at one")]
+    #[test_case("0", "numbering starts at 1"; "zero")]
+    #[test_case("-3", "numbering starts at 1"; "negative")]
+    #[test_case(r#""a-z""#, "cannot parse the pattern"; "invalid")]
+    #[test_case(r#""/(/""#, "invalid regular expression"; "invalid regex")]
+    #[test_case(r#""""#, "no fields specified"; "empty string")]
+    #[test_case("[]", "no fields specified"; "empty list")]
+    #[test_case("{}", "expected a field number"; "wrong type")]
+    fn knife_from_invalid(json: &str, expected: &str) {
+        let err = serde_json::from_str::<Knife>(json).unwrap_err();
+        assert!(err.to_string().contains(expected), "{}", err);
+    }
+
+    #[test]
+    fn matcher_rejects_sub_fields() {
+        let err = serde_json::from_str::<Matcher>(r#""1, 3.2""#).unwrap_err();
+        assert!(
+            err.to_string().contains("only the field numbers"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn sub_delimiter_is_not_serialized() {
+        let knife = Knife::from_str("2.2").unwrap().with_sub_delimiter('=');
+        let json = serde_json::to_string(&knife).unwrap();
+        assert_eq!(json, r#""2.2""#);
+        let knife = serde_json::from_str::<Knife>(&json).unwrap();
+        assert_eq!(knife.sub_delimiter(), crate::SUB_DELIMITER);
+        assert_eq!(knife.with_sub_delimiter('=').extract("a k=v"), ["v"]);
+    }
+
+    #[test]
+    fn empty() {
+        let knife = Knife::from_str("1-3")
+            .unwrap()
+            .difference(&Knife::from_str("-").unwrap())
+            .unwrap();
+        assert!(knife.is_empty());
+        let json = serde_json::to_string(&knife).unwrap();
+        assert_eq!(json, r#""""#);
+        assert!(serde_json::from_str::<Knife>(&json).is_err());
+        let json = serde_json::to_string(knife.matcher()).unwrap();
+        assert!(serde_json::from_str::<Matcher>(&json).is_err());
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Config {
+        #[serde(with = "crate::serialize::structured")]
+        fields: Knife,
+    }
+
+    #[test]
+    fn structured() {
+        let config = Config {
+            fields: Knife::from_str("1, 3-5, 7.2, 9-").unwrap(),
+        };
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(json, r#"{"fields":[1,"3-5","7.2","9-"]}"#);
+        assert_eq!(serde_json::from_str::<Config>(&json).unwrap(), config);
+    }
+}
@@ -0,0 +1,236 @@
+use crate::process::{Cutter, Process};
+use knife::Tokenizer;
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+/// Placeholder in the path template replaced by the value of the key field
+pub const PLACEHOLDER: &str = "{}";
+
+/// Writes the extracted fields to the files named after the value of the key field,
+/// like `awk '{print > $3}'`
+pub struct Split<'a> {
+    knife: &'a Cutter,
+    sep: &'a str,
+    key: usize,
+    template: &'a str,
+    files: Files,
+    line_buffered: bool,
+}
+
+impl<'a> Split<'a> {
+    /// Split by the 1-based `key` field, the paths are created from the `template` by replacing
+    /// the `{}` placeholders with the value of the key, at most `limit` files are kept open
+    pub fn new(
+        knife: &'a Cutter,
+        sep: &'a str,
+        key: usize,
+        template: &'a str,
+        limit: usize,
+        line_buffered: bool,
+    ) -> Self {
+        Self {
+            knife,
+            sep,
+            key,
+            template,
+            files: Files::new(limit),
+            line_buffered,
+        }
+    }
+
+    /// Path of the file for the line, `None` if it has no key field
+    fn path(&self, line: &str) -> Option<PathBuf> {
+        let key = self.knife.tokenizer().tokens(line).nth(self.key - 1)?;
+        Some(PathBuf::from(
+            self.template.replace(PLACEHOLDER, &sanitize(key)),
+        ))
+    }
+}
+
+impl Process for Split<'_> {
+    /// Write the fields extracted from the line to the file, nothing goes to the output
+    fn line(&mut self, line: &str, _out: &mut dyn Write) -> io::Result<bool> {
+        let Some(path) = self.path(line) else {
+            return Ok(false);
+        };
+        let fields = self.knife.extract(line);
+        if fields.is_empty() {
+            return Ok(false);
+        }
+        let file = self.files.get(path)?;
+        file.write_all(fields.join(self.sep).as_bytes())?;
+        file.write_all(b"\n")?;
+        if self.line_buffered {
+            file.flush()?;
+        }
+        Ok(true)
+    }
+
+    fn finish(&mut self, _out: &mut dyn Write) -> io::Result<()> {
+        self.files.close_all()
+    }
+}
+
+/// Make the key safe to use as a part of the path, so it cannot point outside of the directory
+/// given by the template
+fn sanitize(key: &str) -> String {
+    match key {
+        "" | "." | ".." => "_".to_string(),
+        _ => key.replace(['/', '\\', '\0'], "_"),
+    }
+}
+
+/// Open files, when there are too many of them, the least recently used one is closed
+struct Files {
+    limit: usize,
+    open: HashMap<PathBuf, (BufWriter<File>, u64)>,
+    /// Files created by us, they are appended to when opened again
+    created: HashSet<PathBuf>,
+    clock: u64,
+}
+
+impl Files {
+    fn new(limit: usize) -> Self {
+        Self {
+            limit: limit.max(1),
+            open: HashMap::new(),
+            created: HashSet::new(),
+            clock: 0,
+        }
+    }
+
+    /// Get the open file, the files that existed before are overwritten the first time
+    fn get(&mut self, path: PathBuf) -> io::Result<&mut BufWriter<File>> {
+        self.clock += 1;
+        if !self.open.contains_key(&path) {
+            if self.open.len() >= self.limit {
+                self.close_oldest()?;
+            }
+            let file = self.create(&path)?;
+            self.open.insert(path.clone(), (BufWriter::new(file), 0));
+        }
+        let (file, used) = self.open.get_mut(&path).expect("the file was opened");
+        *used = self.clock;
+        Ok(file)
+    }
+
+    fn create(&mut self, path: &Path) -> io::Result<File> {
+        if self.created.contains(path) {
+            return OpenOptions::new()
+                .append(true)
+                .open(path)
+                .map_err(|err| with_path(err, path));
+        }
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).map_err(|err| with_path(err, dir))?;
+        }
+        let file = File::create(path).map_err(|err| with_path(err, path))?;
+        self.created.insert(path.to_path_buf());
+        Ok(file)
+    }
+
+    fn close_oldest(&mut self) -> io::Result<()> {
+        let oldest = self
+            .open
+            .iter()
+            .min_by_key(|(_, (_, used))| *used)
+            .map(|(path, _)| path.clone());
+        if let Some((mut file, _)) = oldest.and_then(|path| self.open.remove(&path)) {
+            file.flush()?;
+        }
+        Ok(())
+    }
+
+    fn close_all(&mut self) -> io::Result<()> {
+        for (_, (mut file, _)) in self.open.drain() {
+            file.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// Add the path to the error message, since it is created from the input
+fn with_path(err: io::Error, path: &Path) -> io::Error {
+    io::Error::new(err.kind(), format!("{}: {}", path.display(), err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sanitize, Split};
+    use crate::process::{Cutter, Process};
+    use knife::Knife;
+    use std::{fs, path::PathBuf, str::FromStr};
+    use test_case::test_case;
+
+    /// Temporary directory unique for the test
+    fn temp_dir(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("knife-split-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn split(knife: &Cutter, key: usize, template: &str, limit: usize, lines: &[&str]) {
+        let mut split = Split::new(knife, " ", key, template, limit, false);
+        let mut out = Vec::new();
+        for line in lines {
+            split.line(line, &mut out).unwrap();
+        }
+        split.finish(&mut out).unwrap();
+        assert!(out.is_empty(), "nothing is printed");
+    }
+
+    fn cutter(spec: &str) -> Cutter {
+        Knife::from_str(spec)
+            .unwrap()
+            .with_tokenizer(Box::new(knife::tokenizer::Whitespace))
+    }
+
+    #[test]
+    fn by_key() {
+        let dir = temp_dir("key");
+        let template = format!("{}/{{}}/access.log", dir.display());
+        let lines = [
+            "a.com GET 200",
+            "b.com GET 404",
+            "a.com POST 500",
+            "incomplete",
+        ];
+        split(&cutter("2-"), 1, &template, 10, &lines);
+
+        let read = |host: &str| fs::read_to_string(dir.join(host).join("access.log")).unwrap();
+        assert_eq!(read("a.com"), "GET 200\nPOST 500\n");
+        assert_eq!(read("b.com"), "GET 404\n");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2, "no key, no file");
+    }
+
+    #[test]
+    fn bounded_open_files() {
+        let dir = temp_dir("bounded");
+        fs::write(dir.join("a.txt"), "old\n").unwrap();
+        let template = format!("{}/{{}}.txt", dir.display());
+        let lines = ["a 1", "b 2", "c 3", "a 4", "b 5"];
+        split(&cutter("2"), 1, &template, 2, &lines);
+
+        let read = |key: &str| fs::read_to_string(dir.join(format!("{}.txt", key))).unwrap();
+        assert_eq!(
+            read("a"),
+            "1\n4\n",
+            "overwritten, then appended when reopened"
+        );
+        assert_eq!(read("b"), "2\n5\n");
+        assert_eq!(read("c"), "3\n");
+    }
+
+    #[test_case("200", "200"; "plain")]
+    #[test_case("../etc/passwd", ".._etc_passwd"; "slashes")]
+    #[test_case("..", "_"; "parent")]
+    #[test_case(".", "_"; "current")]
+    fn sanitized(key: &str, expected: &str) {
+        assert_eq!(sanitize(key), expected);
+    }
+}
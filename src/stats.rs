@@ -0,0 +1,205 @@
+use crate::process::{Cutter, Process};
+use knife::Tokenizer;
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+};
+
+/// How many line numbers are listed for each number of fields
+const EXAMPLES: usize = 5;
+
+/// Lines having the same number of fields
+#[derive(Debug, Default, PartialEq)]
+struct Count {
+    lines: usize,
+    /// Numbers of the first few lines
+    examples: Vec<usize>,
+}
+
+/// Reports the number of fields per line for each of the inputs, to show the shape of the data
+/// and find the lines where the fields are shifted
+pub struct Stats<'a> {
+    knife: &'a Cutter,
+    lines: usize,
+    /// Number of the current line, including the skipped ones
+    number: usize,
+    counts: BTreeMap<usize, Count>,
+}
+
+impl<'a> Stats<'a> {
+    /// Count the fields found by the tokenizer of the `knife`
+    pub fn new(knife: &'a Cutter) -> Self {
+        Self {
+            knife,
+            lines: 0,
+            number: 0,
+            counts: BTreeMap::new(),
+        }
+    }
+
+    /// The most common number of fields, the lower one wins a tie
+    fn mode(&self) -> Option<usize> {
+        self.counts
+            .iter()
+            .rev()
+            .max_by_key(|(_, count)| count.lines)
+            .map(|(&fields, _)| fields)
+    }
+
+    /// Write the report for the input and start counting again
+    fn report(&mut self, name: &str, out: &mut dyn Write) -> io::Result<()> {
+        let (Some(mode), Some(min), Some(max)) = (
+            self.mode(),
+            self.counts.keys().next(),
+            self.counts.keys().next_back(),
+        ) else {
+            self.number = 0;
+            return writeln!(out, "{}: no lines", name);
+        };
+
+        let lines = plural(self.lines, "line");
+        if min == max {
+            writeln!(out, "{}: {}, {}", name, lines, plural(*min, "field"))?;
+        } else {
+            writeln!(
+                out,
+                "{}: {}, {} to {} fields, mostly {}",
+                name, lines, min, max, mode
+            )?;
+            for (&fields, count) in &self.counts {
+                let lines = plural(count.lines, "line");
+                write!(out, "  {}: {}", plural(fields, "field"), lines)?;
+                if fields != mode {
+                    let examples: Vec<String> =
+                        count.examples.iter().map(|x| x.to_string()).collect();
+                    write!(out, ", see {}", examples.join(", "))?;
+                    if count.lines > count.examples.len() {
+                        write!(out, ", ...")?;
+                    }
+                }
+                writeln!(out)?;
+            }
+        }
+
+        self.lines = 0;
+        self.number = 0;
+        self.counts.clear();
+        Ok(())
+    }
+}
+
+/// The count followed by the word, in plural unless the count is 1
+fn plural(count: usize, word: &str) -> String {
+    match count {
+        1 => format!("1 {}", word),
+        n => format!("{} {}s", n, word),
+    }
+}
+
+impl Process for Stats<'_> {
+    fn line(&mut self, line: &str, _out: &mut dyn Write) -> io::Result<bool> {
+        self.lines += 1;
+        self.number += 1;
+        let fields = self.knife.tokenizer().tokens(line).count();
+        let count = self.counts.entry(fields).or_default();
+        count.lines += 1;
+        if count.examples.len() < EXAMPLES {
+            count.examples.push(self.number);
+        }
+        Ok(true)
+    }
+
+    /// The skipped lines are not counted, but the numbers of the following lines stay right
+    fn skip_line(&mut self) {
+        self.number += 1;
+    }
+
+    fn end_input(&mut self, name: &str, out: &mut dyn Write) -> io::Result<bool> {
+        self.report(name, out)?;
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Stats;
+    use crate::process::{Cutter, Process};
+    use knife::Knife;
+    use std::str::FromStr;
+    use test_case::test_case;
+
+    fn stats(text: &str) -> String {
+        let knife: Cutter = Knife::from_str("-")
+            .unwrap()
+            .with_tokenizer(Box::new(knife::tokenizer::Whitespace));
+        let mut stats = Stats::new(&knife);
+        let mut out = Vec::new();
+        for line in text.lines() {
+            stats.line(line, &mut out).unwrap();
+        }
+        stats.end_input("test", &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test_case("", "test: no lines\n"; "empty")]
+    #[test_case("a b\nc d\n", "test: 2 lines, 2 fields\n"; "regular")]
+    #[test_case(
+        "a b c\nd e f\ng h i j\nk l m\n\n",
+        "test: 5 lines, 0 to 4 fields, mostly 3\n  0 fields: 1 line, see 5\n  3 fields: 3 lines\n  4 fields: 1 line, see 3\n";
+        "ragged"
+    )]
+    #[test_case(
+        "a\nb\nc c\nd d\n",
+        "test: 4 lines, 1 to 2 fields, mostly 1\n  1 field: 2 lines\n  2 fields: 2 lines, see 3, 4\n";
+        "tie"
+    )]
+    #[test_case(
+        "a\na\na\n1 2\n1 2\n1 2\n1 2\n1 2\n1 2\n1 2\n",
+        "test: 10 lines, 1 to 2 fields, mostly 2\n  1 field: 3 lines, see 1, 2, 3\n  2 fields: 7 lines\n";
+        "outliers first"
+    )]
+    fn report(text: &str, expected: &str) {
+        assert_eq!(stats(text), expected);
+    }
+
+    #[test]
+    fn many_outliers() {
+        let text = "a b\n".repeat(10) + &"a\n".repeat(7);
+        assert!(stats(&text).contains("  1 field: 7 lines, see 11, 12, 13, 14, 15, ...\n"));
+    }
+
+    #[test]
+    fn skipped_lines() {
+        let knife: Cutter = Knife::from_str("-")
+            .unwrap()
+            .with_tokenizer(Box::new(knife::tokenizer::Whitespace));
+        let mut stats = Stats::new(&knife);
+        let mut out = Vec::new();
+        stats.line("a b", &mut out).unwrap();
+        stats.line("a b", &mut out).unwrap();
+        stats.skip_line();
+        stats.line("a", &mut out).unwrap();
+        stats.end_input("test", &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "test: 3 lines, 1 to 2 fields, mostly 2\n  1 field: 1 line, see 4\n  2 fields: 2 lines\n"
+        );
+    }
+
+    #[test]
+    fn per_input() {
+        let knife: Cutter = Knife::from_str("-")
+            .unwrap()
+            .with_tokenizer(Box::new(knife::tokenizer::Whitespace));
+        let mut stats = Stats::new(&knife);
+        let mut out = Vec::new();
+        stats.line("a b", &mut out).unwrap();
+        stats.end_input("first", &mut out).unwrap();
+        stats.line("a", &mut out).unwrap();
+        stats.end_input("second", &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "first: 1 line, 2 fields\nsecond: 1 line, 1 field\n"
+        );
+    }
+}
@@ -0,0 +1,221 @@
+use crate::process::{Cutter, Process};
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+};
+
+/// Numeric summary of a single field
+#[derive(Debug, Default)]
+struct Column {
+    count: usize,
+    non_numeric: usize,
+    sum: f64,
+    min: f64,
+    max: f64,
+    /// All the values, kept only when the quantiles are needed
+    values: Vec<f64>,
+}
+
+impl Column {
+    fn add(&mut self, value: &str, keep: bool) {
+        let Some(value) = value.parse::<f64>().ok().filter(|x| x.is_finite()) else {
+            self.non_numeric += 1;
+            return;
+        };
+        if self.count == 0 {
+            self.min = value;
+            self.max = value;
+        } else {
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+        self.count += 1;
+        self.sum += value;
+        if keep {
+            self.values.push(value);
+        }
+    }
+}
+
+/// Computes the count, sum, min, max, mean, and the quantiles of the selected fields over all
+/// the lines, the non-numeric values are skipped and counted separately
+pub struct Summary<'a> {
+    knife: &'a Cutter,
+    sep: &'a str,
+    quantiles: &'a [f64],
+    columns: BTreeMap<usize, Column>,
+}
+
+impl<'a> Summary<'a> {
+    /// Summarize the fields selected by the `knife`, the `quantiles` are given as percents,
+    /// like 50 for the median
+    pub fn new(knife: &'a Cutter, sep: &'a str, quantiles: &'a [f64]) -> Self {
+        Self {
+            knife,
+            sep,
+            quantiles,
+            columns: BTreeMap::new(),
+        }
+    }
+}
+
+impl Process for Summary<'_> {
+    fn line(&mut self, line: &str, _out: &mut dyn Write) -> io::Result<bool> {
+        let mut extracted = false;
+        for (field, _, value) in self.knife.extract_spans(line) {
+            let keep = !self.quantiles.is_empty();
+            self.columns.entry(field).or_default().add(value, keep);
+            extracted = true;
+        }
+        Ok(extracted)
+    }
+
+    /// Write the table with a row for each of the fields
+    fn finish(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        if self.columns.is_empty() {
+            return Ok(());
+        }
+
+        let mut header = vec!["field", "count", "sum", "min", "max", "mean"]
+            .into_iter()
+            .map(String::from)
+            .collect::<Vec<_>>();
+        header.extend(self.quantiles.iter().map(|q| format!("p{}", q)));
+        header.push("non-numeric".to_string());
+        writeln!(out, "{}", header.join(self.sep))?;
+
+        for (field, column) in &mut self.columns {
+            let mut row = vec![field.to_string(), column.count.to_string()];
+            if column.count == 0 {
+                let missing = 4 + self.quantiles.len();
+                row.resize(row.len() + missing, "-".to_string());
+            } else {
+                row.push(number(column.sum));
+                row.push(number(column.min));
+                row.push(number(column.max));
+                row.push(number(column.sum / column.count as f64));
+                column.values.sort_by(f64::total_cmp);
+                row.extend(
+                    self.quantiles
+                        .iter()
+                        .map(|&q| number(quantile(&column.values, q))),
+                );
+            }
+            row.push(column.non_numeric.to_string());
+            writeln!(out, "{}", row.join(self.sep))?;
+        }
+        Ok(())
+    }
+}
+
+/// Digits printed after the decimal point, at most
+const PRECISION: usize = 9;
+
+/// Format the number rounded to `PRECISION`, without the trailing zeros, so the rounding errors,
+/// like in 0.1 + 0.2, are not shown
+fn number(value: f64) -> String {
+    let formatted = format!("{:.*}", PRECISION, value);
+    let trimmed = formatted.trim_end_matches('0').trim_end_matches('.');
+    match trimmed {
+        "-0" => "0".to_string(),
+        _ => trimmed.to_string(),
+    }
+}
+
+/// The quantile of the sorted non-empty values for the percent, interpolated linearly
+/// between the closest values
+fn quantile(sorted: &[f64], percent: f64) -> f64 {
+    let rank = percent / 100.0 * (sorted.len() - 1) as f64;
+    let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
+/// Parse the percent for the quantile, between 0 and 100
+pub fn parse_percent(s: &str) -> Result<f64, String> {
+    match s.trim().parse::<f64>() {
+        Ok(percent) if (0.0..=100.0).contains(&percent) => Ok(percent),
+        _ => Err("expected a percent between 0 and 100".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{number, parse_percent, quantile, Summary};
+    use crate::process::{Cutter, Process};
+    use knife::Knife;
+    use std::str::FromStr;
+    use test_case::test_case;
+
+    fn summary(spec: &str, quantiles: &[f64], text: &str) -> String {
+        let knife: Cutter = Knife::from_str(spec)
+            .unwrap()
+            .with_tokenizer(Box::new(knife::tokenizer::Whitespace));
+        let mut summary = Summary::new(&knife, " ", quantiles);
+        let mut out = Vec::new();
+        for line in text.lines() {
+            summary.line(line, &mut out).unwrap();
+        }
+        summary.finish(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn columns() {
+        let text = "a 1 2.5\nb 3 -0.5\nc x 1e3\nd 2\n";
+        assert_eq!(
+            summary("2-3", &[], text),
+            "field count sum min max mean non-numeric\n\
+             2 3 6 1 3 2 1\n\
+             3 3 1002 -0.5 1000 334 0\n"
+        );
+    }
+
+    #[test]
+    fn non_numeric() {
+        assert_eq!(
+            summary("1", &[50.0], "a\nNaN\ninf\n"),
+            "field count sum min max mean p50 non-numeric\n1 0 - - - - - 3\n"
+        );
+    }
+
+    #[test]
+    fn no_fields() {
+        assert_eq!(summary("5", &[], "a b\n"), "");
+    }
+
+    #[test]
+    fn quantiles() {
+        let text: String = (1..=5).map(|x| format!("{}\n", x)).collect();
+        assert_eq!(
+            summary("1", &[0.0, 50.0, 90.0, 100.0], &text),
+            "field count sum min max mean p0 p50 p90 p100 non-numeric\n\
+             1 5 15 1 5 3 1 3 4.6 5 0\n"
+        );
+    }
+
+    #[test_case(&[7.0], 50.0, 7.0; "single value")]
+    #[test_case(&[1.0, 2.0], 50.0, 1.5; "interpolated")]
+    #[test_case(&[1.0, 2.0, 10.0], 100.0, 10.0; "maximum")]
+    fn quantile_of(values: &[f64], percent: f64, expected: f64) {
+        assert_eq!(quantile(values, percent), expected);
+    }
+
+    #[test_case(0.1 + 0.2, "0.3"; "rounding error")]
+    #[test_case(0.15000000000000002, "0.15"; "mean")]
+    #[test_case(1e3, "1000"; "integer")]
+    #[test_case(-0.5, "-0.5"; "negative")]
+    #[test_case(-1e-12, "0"; "negative zero")]
+    #[test_case(1.0 / 3.0, "0.333333333"; "bounded precision")]
+    fn format_number(value: f64, expected: &str) {
+        assert_eq!(number(value), expected);
+    }
+
+    #[test_case("50", Some(50.0); "integer")]
+    #[test_case("99.9", Some(99.9); "fraction")]
+    #[test_case("101", None; "too large")]
+    #[test_case("-1", None; "negative")]
+    #[test_case("x", None; "invalid")]
+    fn percent(s: &str, expected: Option<f64>) {
+        assert_eq!(parse_percent(s).ok(), expected);
+    }
+}
@@ -0,0 +1,324 @@
+use std::ops::Range;
+
+/// Field together with the byte range it occupies in the tokenized string
+pub type Span<'a> = (Range<usize>, &'a str);
+
+/// Splits strings into fields
+///
+/// For every field, `&string[range] == field` holds for the spans returned by the tokenizer.
+pub trait Tokenizer {
+    /// Iterate over the fields of the `string` together with their byte offsets
+    fn spans<'a, 'b>(&'a self, string: &'b str) -> Box<dyn Iterator<Item = Span<'b>> + 'a>
+    where
+        'b: 'a;
+
+    /// Iterate over the fields of the `string`
+    fn tokens<'a, 'b>(&'a self, string: &'b str) -> Box<dyn Iterator<Item = &'b str> + 'a>
+    where
+        'b: 'a,
+    {
+        Box::new(self.spans(string).map(|(_, field)| field))
+    }
+}
+
+impl<T: Tokenizer + ?Sized> Tokenizer for &T {
+    fn spans<'a, 'b>(&'a self, string: &'b str) -> Box<dyn Iterator<Item = Span<'b>> + 'a>
+    where
+        'b: 'a,
+    {
+        (**self).spans(string)
+    }
+
+    fn tokens<'a, 'b>(&'a self, string: &'b str) -> Box<dyn Iterator<Item = &'b str> + 'a>
+    where
+        'b: 'a,
+    {
+        (**self).tokens(string)
+    }
+}
+
+impl<T: Tokenizer + ?Sized> Tokenizer for Box<T> {
+    fn spans<'a, 'b>(&'a self, string: &'b str) -> Box<dyn Iterator<Item = Span<'b>> + 'a>
+    where
+        'b: 'a,
+    {
+        (**self).spans(string)
+    }
+
+    fn tokens<'a, 'b>(&'a self, string: &'b str) -> Box<dyn Iterator<Item = &'b str> + 'a>
+    where
+        'b: 'a,
+    {
+        (**self).tokens(string)
+    }
+}
+
+/// Fields delimited by any Unicode whitespaces, like in `str::split_whitespace`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Whitespace;
+
+impl Tokenizer for Whitespace {
+    fn spans<'a, 'b>(&'a self, string: &'b str) -> Box<dyn Iterator<Item = Span<'b>> + 'a>
+    where
+        'b: 'a,
+    {
+        Box::new(Spans::new(string))
+    }
+
+    fn tokens<'a, 'b>(&'a self, string: &'b str) -> Box<dyn Iterator<Item = &'b str> + 'a>
+    where
+        'b: 'a,
+    {
+        Box::new(string.split_whitespace())
+    }
+}
+
+/// Fields delimited by any ASCII whitespaces, like in `str::split_ascii_whitespace`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct AsciiWhitespace;
+
+impl Tokenizer for AsciiWhitespace {
+    fn spans<'a, 'b>(&'a self, string: &'b str) -> Box<dyn Iterator<Item = Span<'b>> + 'a>
+    where
+        'b: 'a,
+    {
+        Box::new(Spans::with_separator(string, |c| c.is_ascii_whitespace()))
+    }
+
+    fn tokens<'a, 'b>(&'a self, string: &'b str) -> Box<dyn Iterator<Item = &'b str> + 'a>
+    where
+        'b: 'a,
+    {
+        Box::new(string.split_ascii_whitespace())
+    }
+}
+
+/// Fields delimited by a single character, like in the `cut` command,
+/// so unlike with whitespaces, consecutive delimiters mark empty fields
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Delimiter(pub char);
+
+impl Tokenizer for Delimiter {
+    fn spans<'a, 'b>(&'a self, string: &'b str) -> Box<dyn Iterator<Item = Span<'b>> + 'a>
+    where
+        'b: 'a,
+    {
+        let mut start = 0;
+        Box::new(string.split(self.0).map(move |field| {
+            let span = start..start + field.len();
+            start = span.end + self.0.len_utf8();
+            (span, field)
+        }))
+    }
+
+    fn tokens<'a, 'b>(&'a self, string: &'b str) -> Box<dyn Iterator<Item = &'b str> + 'a>
+    where
+        'b: 'a,
+    {
+        Box::new(string.split(self.0))
+    }
+}
+
+/// Fields delimited by Unicode whitespaces, where the whitespaces between the matching
+/// single `'` or double `"` quotes do not delimit the fields
+///
+/// The quotes open only at the start of the field, or after `=`, like in `key="a b"`.
+/// If the field starts and ends with the same quote, the quotes are not part of the field.
+/// The quotes that are not closed are treated as any other characters. There is no support
+/// for escaping the quotes.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Quoted;
+
+impl Tokenizer for Quoted {
+    fn spans<'a, 'b>(&'a self, string: &'b str) -> Box<dyn Iterator<Item = Span<'b>> + 'a>
+    where
+        'b: 'a,
+    {
+        Box::new(QuotedSpans {
+            string,
+            position: 0,
+        })
+    }
+}
+
+/// Fields of fixed widths, counted in characters, the text following the last width
+/// is the last field
+///
+/// The whitespaces padding the fields are not part of the fields.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixedWidth(pub Vec<usize>);
+
+impl Tokenizer for FixedWidth {
+    fn spans<'a, 'b>(&'a self, string: &'b str) -> Box<dyn Iterator<Item = Span<'b>> + 'a>
+    where
+        'b: 'a,
+    {
+        let mut spans = Vec::new();
+        let mut start = 0;
+        for width in &self.0 {
+            if start >= string.len() {
+                break;
+            }
+            let end = string[start..]
+                .char_indices()
+                .nth(*width)
+                .map_or(string.len(), |(i, _)| start + i);
+            spans.push(trimmed(string, start..end));
+            start = end;
+        }
+        if start < string.len() {
+            spans.push(trimmed(string, start..string.len()));
+        }
+        Box::new(spans.into_iter())
+    }
+}
+
+/// Shrink the span so that it does not include the leading and trailing whitespaces
+fn trimmed(string: &str, span: Range<usize>) -> Span<'_> {
+    let field = &string[span.clone()];
+    let start = span.start + (field.len() - field.trim_start().len());
+    let end = span.end - (field.len() - field.trim_end().len());
+    let span = start..end.max(start);
+    (span.clone(), &string[span])
+}
+
+/// Iterator over the whitespace-delimited fields together with their byte offsets,
+/// it splits the string the same way as `str::split_whitespace`
+#[derive(Debug, Clone)]
+pub struct Spans<'a> {
+    string: &'a str,
+    position: usize,
+    is_separator: fn(char) -> bool,
+}
+
+impl<'a> Spans<'a> {
+    /// Create new `Spans` iterating over the fields of the `string`
+    pub fn new(string: &'a str) -> Self {
+        Self::with_separator(string, char::is_whitespace)
+    }
+
+    /// Create new `Spans` where the fields are delimited by the characters matching `is_separator`
+    pub fn with_separator(string: &'a str, is_separator: fn(char) -> bool) -> Self {
+        Self {
+            string,
+            position: 0,
+            is_separator,
+        }
+    }
+}
+
+impl<'a> Iterator for Spans<'a> {
+    type Item = Span<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // skip the leading separators, if nothing else is left, we are done
+        let rest = &self.string[self.position..];
+        let start = self.position + rest.find(|c| !(self.is_separator)(c))?;
+
+        // the field ends at the next separator or at the end of the string
+        let rest = &self.string[start..];
+        let end = start + rest.find(self.is_separator).unwrap_or(rest.len());
+
+        self.position = end;
+        Some((start..end, &self.string[start..end]))
+    }
+}
+
+/// Iterator over the whitespace-delimited fields respecting the quotes
+#[derive(Debug, Clone)]
+struct QuotedSpans<'a> {
+    string: &'a str,
+    position: usize,
+}
+
+impl<'a> Iterator for QuotedSpans<'a> {
+    type Item = Span<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = &self.string[self.position..];
+        let start = self.position + rest.find(|c: char| !c.is_whitespace())?;
+
+        let mut end = self.string.len();
+        let mut quote = None;
+        // where the field would end if the quote is not closed
+        let mut unquoted_end = None;
+        let mut previous = None;
+        for (i, c) in self.string[start..].char_indices() {
+            let after = previous.replace(c);
+            match (quote, c) {
+                // the quotes open only at the start of the field or of the value after `=`
+                (None, '"' | '\'') if matches!(after, None | Some('=')) => quote = Some(c),
+                (None, c) if c.is_whitespace() => {
+                    end = start + i;
+                    break;
+                }
+                (Some(q), c) if q == c => quote = None,
+                (Some(_), c) if c.is_whitespace() && unquoted_end.is_none() => {
+                    unquoted_end = Some(start + i);
+                }
+                _ => (),
+            }
+        }
+        if quote.is_some() {
+            // a lone quote, like in it's, is not a quote at all
+            end = unquoted_end.unwrap_or(end);
+        }
+        self.position = end;
+
+        // strip the quotes surrounding the whole field
+        let field = &self.string[start..end];
+        for q in ['"', '\''] {
+            if field.len() > 1 && field.starts_with(q) && field.ends_with(q) {
+                return Some((start + 1..end - 1, &field[1..field.len() - 1]));
+            }
+        }
+        Some((start..end, field))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AsciiWhitespace, Delimiter, FixedWidth, Quoted, Spans, Tokenizer, Whitespace};
+    use std::ops::Range;
+    use test_case::test_case;
+
+    #[test_case(""; "empty")]
+    #[test_case("   \t "; "only whitespaces")]
+    #[test_case("Mary"; "single word")]
+    #[test_case("Mary had a little lamb."; "sentence")]
+    #[test_case("  Mary\thad \t a\n little   lamb.  "; "mixed whitespaces")]
+    #[test_case("Zażółć\u{3000}gęślą jaźń"; "unicode")]
+    fn same_as_split_whitespace(example: &str) {
+        let fields: Vec<&str> = Spans::new(example).map(|(_, field)| field).collect();
+        let expected: Vec<&str> = example.split_whitespace().collect();
+        assert_eq!(fields, expected);
+    }
+
+    #[test_case(" ab  c\td ", &[1..3, 5..6, 7..8]; "ascii")]
+    #[test_case("żółw\u{3000}ąę", &[0..7, 10..14]; "unicode")]
+    fn spans(example: &str, expected: &[Range<usize>]) {
+        let spans: Vec<_> = Spans::new(example).map(|(span, _)| span).collect();
+        assert_eq!(spans, expected);
+    }
+
+    #[test_case(&Whitespace, "a\u{3000}b  c", &["a", "b", "c"]; "unicode whitespace")]
+    #[test_case(&AsciiWhitespace, "a\u{3000}b  c", &["a\u{3000}b", "c"]; "ascii whitespace")]
+    #[test_case(&Delimiter(','), "a,b,,c", &["a", "b", "", "c"]; "delimiter")]
+    #[test_case(&Delimiter('ł'), "ałbłc", &["a", "b", "c"]; "unicode delimiter")]
+    #[test_case(&Delimiter(','), "", &[""]; "delimiter in empty string")]
+    #[test_case(&Quoted, r#"a "b c" 'd "e' f"#, &["a", "b c", r#"d "e"#, "f"]; "quoted")]
+    #[test_case(&Quoted, r#"k="v w" x"#, &[r#"k="v w""#, "x"]; "quotes inside the field")]
+    #[test_case(&Quoted, r#"a "b c"#, &["a", r#""b"#, "c"]; "unclosed quote")]
+    #[test_case(&Quoted, "msg=it's level='a b' x", &["msg=it's", "level='a b'", "x"]; "apostrophe")]
+    #[test_case(&FixedWidth(vec![3, 2]), "ab cdefg", &["ab", "cd", "efg"]; "fixed width")]
+    #[test_case(&FixedWidth(vec![2, 5]), "żółw", &["żó", "łw"]; "fixed width shorter string")]
+    #[test_case(&FixedWidth(vec![3]), "", &[]; "fixed width empty string")]
+    fn tokens(tokenizer: &dyn Tokenizer, example: &str, expected: &[&str]) {
+        let tokens: Vec<&str> = tokenizer.tokens(example).collect();
+        assert_eq!(tokens, expected);
+
+        for (span, field) in tokenizer.spans(example) {
+            assert_eq!(&example[span], field);
+        }
+    }
+}
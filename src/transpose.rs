@@ -0,0 +1,74 @@
+use crate::process::{Cutter, Process};
+use std::io::{self, Write};
+
+/// Prints the extracted fields transposed, so the fields of each line become a column,
+/// all the lines are kept in memory until the end, the lines without the selected fields
+/// become the columns of the `fill` values
+pub struct Transpose<'a> {
+    knife: &'a Cutter,
+    sep: &'a str,
+    fill: &'a str,
+    rows: Vec<Vec<String>>,
+}
+
+impl<'a> Transpose<'a> {
+    /// The missing fields of the shorter lines are replaced with the `fill`
+    pub fn new(knife: &'a Cutter, sep: &'a str, fill: &'a str) -> Self {
+        Self {
+            knife,
+            sep,
+            fill,
+            rows: Vec::new(),
+        }
+    }
+}
+
+impl Process for Transpose<'_> {
+    fn line(&mut self, line: &str, _out: &mut dyn Write) -> io::Result<bool> {
+        let fields = self.knife.extract(line);
+        let extracted = !fields.is_empty();
+        self.rows
+            .push(fields.into_iter().map(String::from).collect());
+        Ok(extracted)
+    }
+
+    fn finish(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        let width = self.rows.iter().map(Vec::len).max().unwrap_or(0);
+        for i in 0..width {
+            let column: Vec<&str> = self
+                .rows
+                .iter()
+                .map(|row| row.get(i).map_or(self.fill, String::as_str))
+                .collect();
+            writeln!(out, "{}", column.join(self.sep))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Transpose;
+    use crate::process::{Cutter, Process};
+    use knife::Knife;
+    use std::str::FromStr;
+    use test_case::test_case;
+
+    #[test_case("-", "name mary\nage 30\n", "name age\nmary 30\n"; "square")]
+    #[test_case("-", "a 1 x\nb\nc 3\n", "a b c\n1 - 3\nx - -\n"; "ragged")]
+    #[test_case("2", "a 1\nb\nc 3\n", "1 - 3\n"; "selected fields")]
+    #[test_case("2", "a\nb\n", ""; "nothing selected")]
+    #[test_case("-", "", ""; "empty")]
+    fn transpose(spec: &str, text: &str, expected: &str) {
+        let knife: Cutter = Knife::from_str(spec)
+            .unwrap()
+            .with_tokenizer(Box::new(knife::tokenizer::Whitespace));
+        let mut transpose = Transpose::new(&knife, " ", "-");
+        let mut out = Vec::new();
+        for line in text.lines() {
+            transpose.line(line, &mut out).unwrap();
+        }
+        transpose.finish(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+    }
+}
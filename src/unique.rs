@@ -0,0 +1,108 @@
+use crate::process::{Cutter, Process};
+use std::{
+    collections::{HashSet, VecDeque},
+    io::{self, Write},
+    rc::Rc,
+};
+
+/// Which repeated rows are suppressed
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum Mode {
+    /// All the rows printed before
+    All,
+    /// Only the same row as the previous one, like the uniq command
+    Adjacent,
+}
+
+/// Prints the extracted fields, unless the same fields were already printed
+pub struct Unique<'a> {
+    knife: &'a Cutter,
+    sep: &'a str,
+    mode: Mode,
+    limit: Option<usize>,
+    seen: HashSet<Rc<str>>,
+    /// The rows in the order they were seen, to forget the oldest ones when over the limit,
+    /// they are shared with `seen`, so each row is stored once
+    order: VecDeque<Rc<str>>,
+}
+
+impl<'a> Unique<'a> {
+    /// Suppress the repeated rows, if `limit` is given, remember at most that many of them,
+    /// so the repeated rows further apart can be printed again, the `limit` needs to be positive
+    pub fn new(knife: &'a Cutter, sep: &'a str, mode: Mode, limit: Option<usize>) -> Self {
+        Self {
+            knife,
+            sep,
+            mode,
+            limit,
+            seen: HashSet::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Remember the row, return `false` if it was already seen
+    fn insert(&mut self, row: &str) -> bool {
+        if self.mode == Mode::Adjacent {
+            if self.order.back().is_some_and(|last| &**last == row) {
+                return false;
+            }
+            self.order.clear();
+            self.order.push_back(row.into());
+            return true;
+        }
+
+        if self.seen.contains(row) {
+            return false;
+        }
+        let row: Rc<str> = row.into();
+        if let Some(limit) = self.limit {
+            if self.order.len() >= limit {
+                if let Some(oldest) = self.order.pop_front() {
+                    self.seen.remove(&oldest);
+                }
+            }
+            self.order.push_back(Rc::clone(&row));
+        }
+        self.seen.insert(row);
+        true
+    }
+}
+
+impl Process for Unique<'_> {
+    fn line(&mut self, line: &str, out: &mut dyn Write) -> io::Result<bool> {
+        let fields = self.knife.extract(line);
+        if fields.is_empty() {
+            return Ok(false);
+        }
+        let row = fields.join(self.sep);
+        if self.insert(&row) {
+            out.write_all(row.as_bytes())?;
+            out.write_all(b"\n")?;
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Mode, Unique};
+    use crate::process::{Cutter, Process};
+    use knife::Knife;
+    use std::str::FromStr;
+    use test_case::test_case;
+
+    #[test_case(Mode::All, None, "a\nb\nc\n"; "all")]
+    #[test_case(Mode::Adjacent, None, "a\nb\na\nc\nb\n"; "adjacent")]
+    #[test_case(Mode::All, Some(1), "a\nb\na\nc\nb\n"; "limited memory")]
+    fn unique(mode: Mode, limit: Option<usize>, expected: &str) {
+        let knife: Cutter = Knife::from_str("2")
+            .unwrap()
+            .with_tokenizer(Box::new(knife::tokenizer::Whitespace));
+        let mut unique = Unique::new(&knife, " ", mode, limit);
+        let mut out = Vec::new();
+        for line in ["1 a", "2 b", "3 b", "4 a", "5", "6 c", "7 c", "8 b"] {
+            unique.line(line, &mut out).unwrap();
+        }
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+    }
+}
@@ -0,0 +1,297 @@
+//! Tests of the command line tool, running the built binary.
+
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Output, Stdio},
+};
+use test_case::test_case;
+
+/// Run the tool with the arguments and the `input` on Stdin
+fn knife(args: &[&str], input: &str) -> Output {
+    run(Command::new(env!("CARGO_BIN_EXE_knife")).args(args), input)
+}
+
+/// Run the `command` with the `input` on Stdin
+fn run(command: &mut Command, input: impl AsRef<[u8]>) -> Output {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    // the tool can exit before reading everything
+    let _ = stdin.write_all(input.as_ref());
+    drop(stdin);
+    child.wait_with_output().unwrap()
+}
+
+/// Directory for the files used by the test, removed when dropped
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("knife-cli-{}-{}", std::process::id(), name));
+        fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+
+    /// Create the file with the `content` in the directory
+    fn file(&self, name: &str, content: &[u8]) -> PathBuf {
+        let path = self.0.join(name);
+        fs::write(&path, content).unwrap();
+        path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Run the tool with the arguments followed by the `files`
+fn knife_files(args: &[&str], files: &[&Path], input: &str) -> Output {
+    run(
+        Command::new(env!("CARGO_BIN_EXE_knife"))
+            .args(args)
+            .args(files),
+        input,
+    )
+}
+
+#[test]
+fn extract() {
+    let output = knife(&["2"], "a b\nc\nd e");
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "b\ne\n");
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn nothing_extracted() {
+    let output = knife(&["3"], "a b\n");
+    assert!(output.stdout.is_empty());
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn broken_pipe() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_knife"))
+        .arg("1")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // close the downstream end of the pipe before anything is written to it
+    drop(child.stdout.take());
+    let mut stdin = child.stdin.take().unwrap();
+    let _ = stdin.write_all("a b\n".repeat(100_000).as_bytes());
+    drop(stdin);
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(output.status.code(), Some(141));
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn follow_conflicts_with_aggregates() {
+    for mode in ["--stats", "--summary", "--count", "--transpose"] {
+        let output = knife(&["--follow", mode, "1"], "");
+        assert_eq!(output.status.code(), Some(2), "{}", mode);
+    }
+}
+
+#[test]
+fn unique_limit_is_positive() {
+    let output = knife(&["--unique", "--unique-limit", "0", "1"], "a\n");
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn unique_limit_of_adjacent() {
+    let output = knife(&["--unique=adjacent", "--unique-limit", "2", "1"], "a\n");
+    assert_eq!(output.status.code(), Some(2));
+    let output = knife(&["--unique=all", "--unique-limit", "2", "1"], "a\na\n");
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "a\n");
+}
+
+#[test]
+fn sub_options_need_their_mode() {
+    let sub_options: &[&[&str]] = &[
+        &["--negative"],
+        &["--split-path", "{}.txt"],
+        &["--max-open", "3"],
+        &["--join-type", "left"],
+        &["--key", "2"],
+        &["--join-key", "2"],
+        &["--join-fields", "2"],
+        &["--quantiles", "50"],
+        &["--top", "3"],
+        &["--ascending"],
+        &["--unique-limit", "3"],
+        &["--fill", "-"],
+    ];
+    for sub_option in sub_options {
+        let args = [*sub_option, &["1"]].concat();
+        let output = knife(&args, "a b\n");
+        assert_eq!(output.status.code(), Some(2), "{:?}", sub_option);
+    }
+}
+
+#[test]
+fn sub_fields_of_whole_field_modes() {
+    let output = knife(&["--summary", "3.1,3.2"], "a b c:d\n");
+    assert_eq!(output.status.code(), Some(2));
+    let output = knife(&["--join", "j.txt", "--key", "3.2", "1"], "a b c:d\n");
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn content_of_positional_options() {
+    let output = knife(&["--join", "j.txt", "--key", "/:/", "1"], "a b c:d\n");
+    assert_eq!(output.status.code(), Some(2));
+    let output = knife(&["--join", "j.txt", "--join-fields", "/x/", "1"], "a b\n");
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn paragraph_ignores_alias_delimiter() {
+    let config = std::env::temp_dir().join(format!("knife-cli-{}.toml", std::process::id()));
+    std::fs::write(&config, "second = { fields = \"2\", delimiter = \":\" }\n").unwrap();
+    let output = run(
+        Command::new(env!("CARGO_BIN_EXE_knife"))
+            .args(["--paragraph", "@second"])
+            .env("KNIFE_CONFIG", &config),
+        "a:b c\nd\n\ne f\n",
+    );
+    std::fs::remove_file(&config).unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "c\nf\n");
+}
+
+#[test_case("--follow"; "follow")]
+#[test_case("--stats"; "stats")]
+fn paragraph_conflicts(option: &str) {
+    let output = knife(&["--paragraph", option, "1"], "");
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn logfmt_sub_fields() {
+    let output = knife(&["--logfmt", "--sub-delimiter", "=", "2.2"], "a k=v=w\n");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "v\n");
+}
+
+#[test]
+fn logfmt_conflicts_with_explain() {
+    let output = knife(&["--logfmt", "--explain", "user"], "");
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test_case("x"; "letter")]
+#[test_case("a-z"; "range of letters")]
+fn keys_without_logfmt(fields: &str) {
+    let output = knife(&[fields], "a b\n");
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("cannot parse the pattern"), "{}", stderr);
+    assert!(stderr.contains("--logfmt"), "{}", stderr);
+}
+
+#[test]
+fn in_place() {
+    let dir = TempDir::new("in-place");
+    let path = dir.file("a.txt", b"a b\nc d\n");
+    let output = knife_files(&["-i", "2"], &[&path], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty());
+    assert_eq!(fs::read_to_string(&path).unwrap(), "b\nd\n");
+    assert_eq!(fs::read_dir(&dir.0).unwrap().count(), 1, "no leftovers");
+}
+
+#[test]
+fn in_place_backup() {
+    let dir = TempDir::new("in-place-backup");
+    let path = dir.file("a.txt", b"a b\n");
+    let output = knife_files(&["-i.bak", "2"], &[&path], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(fs::read_to_string(&path).unwrap(), "b\n");
+    assert_eq!(
+        fs::read_to_string(dir.0.join("a.txt.bak")).unwrap(),
+        "a b\n"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn in_place_keeps_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = TempDir::new("in-place-permissions");
+    let path = dir.file("a.sh", b"a b\n");
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o750)).unwrap();
+    let output = knife_files(&["-i", "1"], &[&path], "");
+    assert_eq!(output.status.code(), Some(0));
+    let mode = fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o750);
+}
+
+#[test]
+fn in_place_failure() {
+    let dir = TempDir::new("in-place-failure");
+    let bad = dir.file("bad.txt", b"a b\n\xff\n");
+    let good = dir.file("good.txt", b"c d\n");
+    let missing = dir.0.join("missing.txt");
+    let output = knife_files(&["-i.bak", "1"], &[&bad, &missing, &good], "");
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(fs::read(&bad).unwrap(), b"a b\n\xff\n", "left untouched");
+    assert!(!dir.0.join("bad.txt.bak").exists());
+    assert_eq!(
+        fs::read_to_string(&good).unwrap(),
+        "c\n",
+        "the rest is edited"
+    );
+    assert_eq!(fs::read_dir(&dir.0).unwrap().count(), 3, "no leftovers");
+}
+
+#[test_case(&["-i", "1"]; "no files")]
+#[test_case(&["-i", "1", "-"]; "dash")]
+fn in_place_rejects_stdin(args: &[&str]) {
+    let output = knife(args, "a b\n");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn missing_file() {
+    let dir = TempDir::new("missing-file");
+    let path = dir.file("a.txt", b"a b\n");
+    let output = knife_files(&["2"], &[&dir.0.join("missing.txt"), &path], "");
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "b\n");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("missing.txt"), "{}", stderr);
+}
+
+#[test]
+fn stdin_between_files() {
+    let dir = TempDir::new("stdin-between-files");
+    let first = dir.file("first.txt", b"a 1\n");
+    let last = dir.file("last.txt", b"c 3\n");
+    let output = knife_files(&["2"], &[&first, Path::new("-"), &last], "b 2\n");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "1\n2\n3\n");
+}
+
+#[test]
+fn invalid_utf8() {
+    let output = run(
+        Command::new(env!("CARGO_BIN_EXE_knife")).arg("1"),
+        b"a b\n\xff x\nc d\n",
+    );
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "a\nc\n");
+}